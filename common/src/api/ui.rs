@@ -1,13 +1,80 @@
 //! TODO: traits for how elements should render idk
 
-pub trait Tick {
-    fn tick(&mut self);
+use crate::sim::logic::{
+    command::Command, fractal::Fractal, fragment::FragmentRegistry, path::TilePos, tile::SubTile,
+};
+use glam::IVec2;
+use std::collections::HashMap;
+
+/// something the player asked for, gathered by a frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    /// changes the game, the same way no matter where it came from.
+    /// a tick runs right away, even while paused
+    Command(Command),
+    /// stops or resumes the automatic ticks
+    Pause,
+    /// stops the game loop
+    Quit,
 }
 
-pub trait Context: Default + Tick {}
+/// draws a fractal as a triangle of text, `depth` layers down.
+///
+/// each tile is drawn with its fragment's symbol.
+/// tiles too small to draw are `+`, and unknown fragments are `?`.
+pub fn text_view(fractal: &Fractal, fragments: &FragmentRegistry, depth: u8) -> String {
+    let symbols = symbols(fractal, fragments, depth);
+    let symbol = |x, y, flop| {
+        let pos = TilePos {
+            depth,
+            pos: IVec2 { x, y },
+            flop,
+        };
+        symbols.get(&pos).copied().unwrap_or('.')
+    };
 
-pub trait Draw {
-    type Context: Context;
+    let height = 1 << depth;
+    let mut out = String::new();
+    for row in 0..height {
+        out.push_str(&" ".repeat((height - 1 - row) as usize));
+        for x in 0..=row {
+            out.push(symbol(x, row, false));
+            if x < row {
+                out.push(symbol(x, row - 1, true));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
 
-    fn draw(&self, ctx: Self::Context);
+/// finds the symbol to draw at every position `depth` layers down.
+fn symbols(fractal: &Fractal, fragments: &FragmentRegistry, depth: u8) -> HashMap<TilePos, char> {
+    let mut out = HashMap::new();
+    for (mut pos, tile, _fill) in fractal.leaves() {
+        if pos.depth > depth {
+            // too small to draw
+            while pos.depth > depth {
+                pos.pop_back();
+            }
+            out.insert(pos, '+');
+            continue;
+        }
+        let symbol = fragments
+            .get(tile.id)
+            .map_or('?', |fragment| fragment.appearance.symbol);
+        let mut stack = vec![pos];
+        while let Some(pos) = stack.pop() {
+            if pos.depth == depth {
+                out.insert(pos, symbol);
+                continue;
+            }
+            for subtile in SubTile::QUAD {
+                let mut child = pos;
+                child.push_back(subtile);
+                stack.push(child);
+            }
+        }
+    }
+    out
 }
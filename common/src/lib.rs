@@ -1,7 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
 
-use api::ui::{Context, Draw};
-use sim::logic::{fractal::Fractal, path::TilePos, tile::Tile};
+use api::ui::Input;
+use sim::{
+    logic::{
+        command::{CommandError, CommandHistory},
+        factory::{FractoryMeta, TickReport},
+        planet::{Planet, PlanetCache},
+        replay::Replay,
+    },
+    schedule::TickScheduler,
+};
 
 #[cfg(test)]
 mod tests;
@@ -9,18 +20,89 @@ mod tests;
 pub mod api;
 pub mod sim;
 
+/// how many ticks the game loop runs per second, unless paused
+pub const TICK_PER_SEC: f32 = 5.0;
+
+/// the longest the game loop waits between frames
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+/// a frontend, which shows the game to the player and gathers their input.
+///
+/// everything else, such as when to tick, is handled by [`run`].
 pub trait Game {
-    type GlobalContext: Context;
-    type TileElement: From<Tile> + Draw;
+    /// returns everything the player asked for since the last call.
+    /// should not block, since ticks can't happen in the meantime.
+    fn input(&mut self, meta: &FractoryMeta, planet: &Planet) -> Vec<Input>;
+
+    /// called right after every tick.
+    fn on_tick(&mut self, _meta: &FractoryMeta, _report: &TickReport) {}
+
+    /// called when one of the player's commands couldn't be executed.
+    fn on_error(&mut self, _meta: &FractoryMeta, _error: CommandError) {}
+
+    /// where to record every command and tick, if anywhere.
+    fn replay(&mut self) -> Option<&mut Replay> {
+        None
+    }
+
+    /// shows the current state of the game.
+    /// called once per frame, whether or not anything changed.
+    fn draw(&mut self, meta: &FractoryMeta, planet: &Planet);
 }
 
-pub fn run<T: Game>() -> Result<(), ()> {
-    let mut ctx = T::GlobalContext::default();
-    let mut tree = Fractal::new_binary();
+/// runs the game loop until the frontend asks to quit.
+///
+/// ticks happen at a fixed rate, no matter how often the frontend draws.
+/// fails if the fractory's planet or biome isn't in `planets`.
+pub fn run<G: Game>(
+    game: &mut G,
+    planets: &PlanetCache,
+    meta: &mut FractoryMeta,
+) -> io::Result<()> {
+    let not_found = |what| io::Error::new(io::ErrorKind::NotFound, what);
+    let planet = planets
+        .get(&meta.planet)
+        .ok_or_else(|| not_found(format!("planet {:?} is not loaded", meta.planet)))?;
+    let biome = planet
+        .biomes()
+        .get(&meta.biome)
+        .ok_or_else(|| not_found(format!("biome {:?} is not loaded", meta.biome)))?;
 
-    const TICK_PER_SEC: f32 = 5.0;
+    let mut scheduler = TickScheduler::new(TICK_PER_SEC).expect("TICK_PER_SEC is a valid rate");
+    let mut history = CommandHistory::default();
+    let mut last_frame = Instant::now();
+    loop {
+        for input in game.input(meta, planet) {
+            match input {
+                Input::Command(command) => {
+                    let result = meta.execute(command, planet.fragments(), biome, &mut history);
+                    if let (Ok(_), Some(replay)) = (&result, game.replay()) {
+                        replay.record(command);
+                    }
+                    match result {
+                        Ok(Some(report)) => game.on_tick(meta, &report),
+                        Ok(None) => {}
+                        Err(error) => game.on_error(meta, error),
+                    }
+                }
+                Input::Pause => scheduler.toggle_pause(),
+                Input::Quit => return Ok(()),
+            }
+        }
 
-    let mut tiles = HashMap::from([(TilePos::UNIT, T::TileElement::from(tree.get(TilePos::UNIT)))]);
+        // scheduled ticks can't be undone on their own,
+        // so undoing a command also undoes every tick since then
+        let now = Instant::now();
+        for _ in 0..scheduler.advance(now - last_frame) {
+            let report = meta.tick(planet.fragments(), biome);
+            if let Some(replay) = game.replay() {
+                replay.record_tick();
+            }
+            game.on_tick(meta, &report);
+        }
+        last_frame = now;
 
-    Ok(())
+        game.draw(meta, planet);
+        thread::sleep(scheduler.until_next_tick().min(FRAME_TIME));
+    }
 }
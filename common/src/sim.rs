@@ -1,2 +1,3 @@
 pub mod io;
 pub mod logic;
+pub mod schedule;
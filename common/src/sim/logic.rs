@@ -1,8 +1,12 @@
 pub mod actions;
+pub mod command;
 pub mod factory;
 pub mod fractal;
+pub mod fragment;
+pub mod mission;
 pub mod orientation;
 pub mod path;
 pub mod planet;
+pub mod replay;
 pub mod tile;
 pub mod tree;
@@ -3,18 +3,42 @@ use super::{
     path::{TileOffset, TilePos},
     tile::Tile,
 };
+use serde::{Deserialize, Serialize};
+
+/// how strongly an action insists on happening.
+///
+/// when two actions conflict, the one with the higher priority wins.
+/// ties are resolved by the biome's collision policy.
+pub type Priority = u8;
 
 /// a complete action that can be done to the tree,
 /// where T is a position that is either relative (TileOffset)
 /// or absolute (TilePos)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TargetedAction<T> {
     pub target: T,
     pub act: TileAction<T>,
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+impl<T> TargetedAction<T> {
+    /// an action with the lowest priority.
+    pub fn new(target: T, act: TileAction<T>) -> Self {
+        Self {
+            target,
+            act,
+            priority: 0,
+        }
+    }
+
+    pub fn with_priority(self, priority: Priority) -> Self {
+        Self { priority, ..self }
+    }
 }
 
 /// action to do at an exact node
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum TileAction<T> {
     /// moves this fragment to another tile
     Move(T, Transform),
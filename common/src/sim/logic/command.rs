@@ -0,0 +1,260 @@
+#[cfg(test)]
+mod tests;
+
+use super::{
+    factory::{active::ActiveTiles, stats::Throughput, Fractory, TickReport},
+    fragment::FragmentRegistry,
+    mission::MissionProgress,
+    orientation::Transform,
+    path::TilePos,
+    planet::Biome,
+    tile::Tile,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+};
+
+/// something a player, a script, or a replay does to a fractory.
+///
+/// every frontend goes through these, so they all behave the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// replaces the tile at a position with any tile in the fractal, without touching the inventory
+    SetTile(TilePos, Tile),
+    /// rotates the whole subtree at a position, clockwise or counterclockwise
+    Rotate { pos: TilePos, clockwise: bool },
+    /// reflects the whole subtree at a position
+    Flip(TilePos),
+    /// toggles whether a position will be activated next tick
+    ToggleActive(TilePos),
+    /// simulates 1 tick
+    Tick,
+    /// moves the fragment at a position into the inventory
+    Store(TilePos),
+    /// takes a fragment out of the inventory, and places it upright onto an empty position
+    Place(TilePos, usize),
+    /// goes back to how things were before the last command
+    Undo,
+}
+
+/// why a command couldn't be executed.
+/// nothing changes when a command fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError {
+    /// the position is outside of the fractal
+    OutOfBounds(TilePos),
+    /// the tile doesn't exist, or is oriented in a way it can't be
+    InvalidTile(Tile),
+    /// there's no fragment at the position to store
+    NothingToStore(TilePos),
+    /// the position has to be empty
+    Occupied(TilePos),
+    /// the inventory doesn't have any of this fragment
+    NotInInventory(usize),
+    /// there are no commands left to undo
+    NothingToUndo,
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds(pos) => write!(f, "{pos:#} is out of bounds"),
+            Self::InvalidTile(tile) => write!(f, "{tile:?} is not a valid tile"),
+            Self::NothingToStore(pos) => write!(f, "there is nothing to store at {pos}"),
+            Self::Occupied(pos) => write!(f, "{pos} is not empty"),
+            Self::NotInInventory(id) => write!(f, "there is no fragment #{id} in the inventory"),
+            Self::NothingToUndo => write!(f, "there is nothing to undo"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// progress on each mission, by name.
+pub(super) type Missions = BTreeMap<String, MissionProgress>;
+
+/// everything a command can change.
+///
+/// the fractal's library only ever grows, so its root is enough to restore it.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    root: Tile,
+    activated: ActiveTiles,
+    inventory: BTreeMap<usize, usize>,
+    throughput: Throughput,
+    missions: Missions,
+}
+
+/// the commands that can be undone, as snapshots of the fractory before each one.
+#[derive(Debug, Clone)]
+pub struct CommandHistory {
+    snapshots: VecDeque<Snapshot>,
+    /// how many snapshots to keep before forgetting the oldest ones
+    limit: usize,
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl CommandHistory {
+    pub const DEFAULT_LIMIT: usize = 256;
+
+    pub fn new(limit: usize) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// how many commands can be undone.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    fn push(&mut self, snapshot: Snapshot) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.limit {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+}
+
+impl Fractory {
+    /// executes a command, remembering how to undo it in `history`.
+    ///
+    /// returns what happened during the tick, if the command was a tick.
+    pub fn execute(
+        &mut self,
+        command: Command,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        history: &mut CommandHistory,
+    ) -> Result<Option<TickReport>, CommandError> {
+        self.execute_tracking(command, fragments, biome, history, &mut Missions::new())
+    }
+
+    /// like `execute`, but also remembers and restores progress on `missions`.
+    /// the missions themselves are left for the caller to update.
+    pub(super) fn execute_tracking(
+        &mut self,
+        command: Command,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        history: &mut CommandHistory,
+        missions: &mut Missions,
+    ) -> Result<Option<TickReport>, CommandError> {
+        if command == Command::Undo {
+            let snapshot = history
+                .snapshots
+                .pop_back()
+                .ok_or(CommandError::NothingToUndo)?;
+            *missions = self.restore(snapshot);
+            return Ok(None);
+        }
+
+        self.check(command)?;
+        history.push(self.snapshot(missions));
+
+        match command {
+            Command::SetTile(pos, tile) => drop(self.fractal.set(pos, tile)),
+            Command::Rotate { pos, clockwise } => {
+                let transform = if clockwise {
+                    Transform::KR
+                } else {
+                    Transform::KL
+                };
+                self.transform_at(pos, transform);
+            }
+            Command::Flip(pos) => self.transform_at(pos, Transform::FU),
+            Command::ToggleActive(pos) => self.toggle_activation(pos),
+            Command::Tick => return Ok(Some(self.tick(fragments, biome))),
+            Command::Store(pos) => self.store(pos),
+            Command::Place(pos, id) => {
+                let count = self.inventory.get_mut(&id).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    self.inventory.remove(&id);
+                }
+                let orient = self.fractal.get_info(id).symmetries.into();
+                self.fractal.set(pos, Tile { id, orient });
+            }
+            Command::Undo => unreachable!(),
+        }
+        Ok(None)
+    }
+
+    /// makes sure a command can be executed, without changing anything.
+    fn check(&self, command: Command) -> Result<(), CommandError> {
+        let pos = match command {
+            Command::SetTile(pos, _)
+            | Command::Rotate { pos, .. }
+            | Command::Flip(pos)
+            | Command::ToggleActive(pos)
+            | Command::Store(pos)
+            | Command::Place(pos, _) => pos,
+            Command::Tick | Command::Undo => return Ok(()),
+        };
+        if !pos.is_valid() {
+            return Err(CommandError::OutOfBounds(pos));
+        }
+
+        match command {
+            Command::SetTile(_, tile) => {
+                let info = self.fractal.library.get(tile.id);
+                if info.map(|info| info.symmetries) != Some(tile.orient.symmetries()) {
+                    return Err(CommandError::InvalidTile(tile));
+                }
+            }
+            Command::Store(pos) => {
+                let tile = self.fractal.get(pos);
+                if !self.fractal.get_info(tile.id).fill.is_full() {
+                    return Err(CommandError::NothingToStore(pos));
+                }
+            }
+            Command::Place(pos, id) => {
+                if self.inventory.get(&id).copied().unwrap_or(0) == 0 {
+                    return Err(CommandError::NotInInventory(id));
+                }
+                if self.fractal.get(pos) != Tile::SPACE {
+                    return Err(CommandError::Occupied(pos));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self, missions: &Missions) -> Snapshot {
+        Snapshot {
+            root: self.fractal.root,
+            activated: self.activated.clone(),
+            inventory: self.inventory.clone(),
+            throughput: self.throughput.clone(),
+            missions: missions.clone(),
+        }
+    }
+
+    /// brings back everything in the snapshot, and returns the missions to bring back.
+    fn restore(&mut self, snapshot: Snapshot) -> Missions {
+        self.fractal.root = snapshot.root;
+        self.activated = snapshot.activated;
+        self.inventory = snapshot.inventory;
+        self.throughput = snapshot.throughput;
+        snapshot.missions
+    }
+}
@@ -0,0 +1,180 @@
+use super::*;
+use crate::sim::logic::{orientation::Orient, planet::Planet};
+use glam::IVec2;
+
+fn pos(x: i32, y: i32) -> TilePos {
+    TilePos {
+        depth: 2,
+        pos: IVec2 { x, y },
+        flop: false,
+    }
+}
+
+/// everything that undo should bring back
+fn state(fractory: &Fractory) -> (Tile, Vec<TilePos>, BTreeMap<usize, usize>) {
+    (
+        fractory.fractal.root,
+        fractory.activated.iter().collect(),
+        fractory.inventory.clone(),
+    )
+}
+
+#[test]
+fn test_undo() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&"Landing Zone".into()).unwrap();
+    let x = fragments.tile("X").unwrap();
+    let spinner = fragments.tile("Spinner").unwrap();
+
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let mut history = CommandHistory::default();
+    let commands = [
+        Command::SetTile(pos(0, 3), x),
+        Command::SetTile(pos(1, 3), spinner),
+        Command::Rotate {
+            pos: pos(1, 3),
+            clockwise: true,
+        },
+        Command::Flip(pos(1, 3)),
+        Command::ToggleActive(pos(0, 3)),
+        Command::Store(pos(1, 3)),
+        Command::Place(pos(2, 3), spinner.id),
+        Command::Tick,
+    ];
+
+    let mut states = vec![];
+    for command in commands {
+        states.push(state(&fractory));
+        let report = fractory
+            .execute(command, fragments, biome, &mut history)
+            .unwrap();
+        assert_eq!(report.is_some(), command == Command::Tick);
+        assert_ne!(state(&fractory), *states.last().unwrap(), "{command:?}");
+    }
+    assert_eq!(fractory.fractal.get(pos(2, 3)), spinner);
+    assert_eq!(history.len(), commands.len());
+
+    while let Some(expected) = states.pop() {
+        fractory
+            .execute(Command::Undo, fragments, biome, &mut history)
+            .unwrap();
+        assert_eq!(state(&fractory), expected);
+    }
+    assert_eq!(
+        fractory.execute(Command::Undo, fragments, biome, &mut history),
+        Err(CommandError::NothingToUndo)
+    );
+}
+
+#[test]
+fn test_errors() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&"Landing Zone".into()).unwrap();
+    let spinner = fragments.tile("Spinner").unwrap();
+
+    let mut fractory = Fractory::new_xyyy(fragments);
+    fractory.fractal.set(TilePos::UNIT, Tile::SPACE);
+    fractory.fractal.set(pos(0, 3), spinner);
+    let mut history = CommandHistory::default();
+    let before = state(&fractory);
+
+    let outside = TilePos {
+        depth: 2,
+        pos: IVec2 { x: 4, y: 3 },
+        flop: false,
+    };
+    let asymmetric_iso = Tile {
+        orient: Orient::Iso,
+        ..spinner
+    };
+    let missing = Tile {
+        id: 1000,
+        ..Tile::ONE
+    };
+    let cases = [
+        (Command::Flip(outside), CommandError::OutOfBounds(outside)),
+        (
+            Command::SetTile(pos(1, 3), asymmetric_iso),
+            CommandError::InvalidTile(asymmetric_iso),
+        ),
+        (
+            Command::SetTile(pos(1, 3), missing),
+            CommandError::InvalidTile(missing),
+        ),
+        (
+            Command::Store(pos(1, 3)),
+            CommandError::NothingToStore(pos(1, 3)),
+        ),
+        (
+            Command::Place(pos(1, 3), spinner.id),
+            CommandError::NotInInventory(spinner.id),
+        ),
+    ];
+    for (command, error) in cases {
+        assert_eq!(
+            fractory.execute(command, fragments, biome, &mut history),
+            Err(error)
+        );
+    }
+
+    fractory
+        .execute(Command::Store(pos(0, 3)), fragments, biome, &mut history)
+        .unwrap();
+    fractory
+        .execute(
+            Command::SetTile(pos(1, 3), spinner),
+            fragments,
+            biome,
+            &mut history,
+        )
+        .unwrap();
+    assert_eq!(
+        fractory.execute(
+            Command::Place(pos(1, 3), spinner.id),
+            fragments,
+            biome,
+            &mut history
+        ),
+        Err(CommandError::Occupied(pos(1, 3)))
+    );
+
+    // failed commands can't be undone, since they didn't do anything
+    assert_eq!(history.len(), 2);
+    for _ in 0..2 {
+        fractory
+            .execute(Command::Undo, fragments, biome, &mut history)
+            .unwrap();
+    }
+    assert_eq!(state(&fractory), before);
+}
+
+#[test]
+fn test_history_limit() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&"Landing Zone".into()).unwrap();
+
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let mut history = CommandHistory::new(3);
+    for _ in 0..5 {
+        fractory
+            .execute(
+                Command::ToggleActive(pos(0, 3)),
+                fragments,
+                biome,
+                &mut history,
+            )
+            .unwrap();
+    }
+    assert_eq!(history.len(), 3);
+    for _ in 0..3 {
+        fractory
+            .execute(Command::Undo, fragments, biome, &mut history)
+            .unwrap();
+    }
+    assert!(history.is_empty());
+    // the 2 oldest toggles are forgotten
+    assert!(!fractory.activated.contains(pos(0, 3)));
+}
@@ -1,22 +1,34 @@
 // TODO: fractory game logic
 
+#[cfg(test)]
+mod tests;
+
+pub mod active;
+pub mod memo;
+pub mod stats;
+
+use self::{active::ActiveTiles, memo::TickMemo, stats::Throughput};
 use super::{
     actions::{TargetedAction, TileAction},
+    command::{Command, CommandError, CommandHistory},
     fractal::Fractal,
+    fragment::FragmentRegistry,
+    mission::MissionProgress,
     orientation::Transform,
-    path::{TileOffset, TilePos},
-    planet::{Behavior, Biome, BiomeCache, BiomeId, Filter, Planet, PlanetCache, PlanetId},
-    tile::Tile,
-    tree::collision::RawMoveList,
+    path::{Position, TileOffset, TilePos},
+    planet::{Biome, BiomeCache, BiomeId, Planet, PlanetCache, PlanetId},
+    tile::{SubTile, Tile},
+    tree::collision::{CleanMoveList, CollisionPolicy, RawMoveList},
 };
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
-    io,
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
     rc::Rc,
 };
 
-use glam::IVec2;
-
 // TODO: remove comment
 // /// A single planet, containing information about the fragments within it.
 // #[derive(Debug)]
@@ -67,58 +79,90 @@ use glam::IVec2;
 //     }
 // }
 
-#[derive(Debug)]
-pub struct ActiveTiles(HashSet<TilePos>);
-
-impl ActiveTiles {
-    fn new() -> Self {
-        Self(HashSet::new())
-    }
-
-    pub fn contains(&self, pos: TilePos) -> bool {
-        self.0.contains(&pos)
-    }
-
-    /// activates a position.
-    ///
-    /// returns true if the position used to be inactive.
-    pub fn activate(&mut self, pos: TilePos) -> bool {
-        self.0.insert(pos)
-    }
-
-    /// deactivates a position.
-    ///
-    /// returns true if the position used to be active.
-    pub fn deactivate(&mut self, pos: TilePos) -> bool {
-        self.0.remove(&pos)
-    }
-
-    /// toggles whether a position is active or inactive.
-    ///
-    /// returns true if the position is now active.
-    pub fn toggle(&mut self, pos: TilePos) -> bool {
-        let is_new = self.0.insert(pos);
-        if !is_new {
-            self.0.remove(&pos);
-        }
-        is_new
-    }
-}
-
 /// practically a save file
 #[derive(Debug)]
 pub struct FractoryMeta {
     pub fractory: Fractory,
     pub planet: PlanetId,
     pub biome: BiomeId,
+    /// Progress on each of the biome's missions, by name.
+    pub missions: BTreeMap<String, MissionProgress>,
     // pub creation_date: Instant,
     // pub age: Duration,
     // pub name: String,
 }
 
 impl FractoryMeta {
-    pub fn load(f: &str) -> io::Result<Self> {
-        todo!()
+    /// reads a fractory written by [`Self::save`].
+    /// its planet has to be loaded already, since the fractal is rebuilt from its fragments.
+    pub fn load(path: impl AsRef<Path>, planets: &PlanetCache) -> io::Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?, planets)
+    }
+
+    /// writes the fractory and the progress on its missions to a file, as RON.
+    /// throughput isn't saved, so it starts over after loading.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_ron())
+    }
+
+    fn to_ron(&self) -> String {
+        let fractory = &self.fractory;
+        let save = SaveFile {
+            planet: self.planet.as_str().to_owned(),
+            biome: self.biome.as_str().to_owned(),
+            leaves: fractory
+                .fractal
+                .leaves()
+                .map(|(pos, tile, _)| (pos.to_string(), tile))
+                .collect(),
+            activated: fractory
+                .activated
+                .iter()
+                .map(|pos| pos.to_string())
+                .collect(),
+            inventory: fractory.inventory.clone(),
+            missions: self.missions.clone(),
+        };
+        ron::ser::to_string_pretty(&save, PrettyConfig::default())
+            .expect("save files only hold plain data")
+    }
+
+    fn from_ron(text: &str, planets: &PlanetCache) -> io::Result<Self> {
+        let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+        let save: SaveFile = ron::from_str(text).map_err(|error| invalid(error.to_string()))?;
+        let planet_id = PlanetId::from(save.planet);
+        let planet = planets.get(&planet_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("planet {planet_id:?} is not loaded"),
+            )
+        })?;
+        let fragments = planet.fragments();
+        let parse_pos = |pos: &str| {
+            pos.parse::<TilePos>()
+                .map_err(|error| invalid(error.to_string()))
+        };
+
+        let fractal = fragments
+            .build_fractal()
+            .map_err(|error| invalid(error.to_string()))?;
+        let mut fractory = Fractory::new(fractal);
+        for (pos, tile) in save.leaves {
+            if tile.id == 0 || tile.id >= fragments.len() {
+                return Err(invalid(format!("{tile:?} is not a fragment")));
+            }
+            fractory.fractal.set(parse_pos(&pos)?, tile);
+        }
+        for pos in save.activated {
+            fractory.activated.activate(parse_pos(&pos)?);
+        }
+        fractory.inventory = save.inventory;
+        Ok(Self {
+            fractory,
+            planet: planet_id,
+            biome: BiomeId::from(save.biome),
+            missions: save.missions,
+        })
     }
 
     /// TODO: FOR TESTING PURPOSES
@@ -127,58 +171,269 @@ impl FractoryMeta {
         let planet_id = xyyy.default_id();
         // let biome_id = BiomeId::from("Spinless");
         let biome_id = BiomeId::from("Landing Zone");
+        let fractory = Fractory::new_xyyy(xyyy.fragments());
         planets.register(planet_id.clone(), xyyy);
         Self {
-            fractory: Fractory::new_xyyy(),
+            fractory,
             planet: planet_id,
             biome: biome_id,
+            missions: BTreeMap::new(),
+        }
+    }
+
+    /// Simulates 1 tick of the Fractory,
+    /// then updates the progress on every mission in the biome.
+    pub fn tick(&mut self, fragments: &FragmentRegistry, biome: &Biome) -> TickReport {
+        let report = self.fractory.tick(fragments, biome);
+        self.update_missions(biome, &report);
+        report
+    }
+
+    /// Executes a command on the Fractory,
+    /// then updates the progress on every mission in the biome if it ticked.
+    /// Undoing a command also undoes any progress it made.
+    pub fn execute(
+        &mut self,
+        command: Command,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        history: &mut CommandHistory,
+    ) -> Result<Option<TickReport>, CommandError> {
+        let report = self.fractory.execute_tracking(
+            command,
+            fragments,
+            biome,
+            history,
+            &mut self.missions,
+        )?;
+        if let Some(report) = &report {
+            self.update_missions(biome, report);
+        }
+        Ok(report)
+    }
+
+    fn update_missions(&mut self, biome: &Biome, report: &TickReport) {
+        for mission in biome.missions() {
+            self.missions
+                .entry(mission.name.clone())
+                .or_insert_with(|| mission.start())
+                .update(mission, &self.fractory, report);
         }
     }
 }
 
+/// What a save file holds.
+///
+/// The fractal is saved leaf by leaf, since the ids of the tiles in between
+/// depend on the order they were made in.
+/// Leaves have the same ids as their fragments, so those stay valid with the planet.
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveFile {
+    planet: String,
+    biome: String,
+    leaves: Vec<(String, Tile)>,
+    activated: Vec<String>,
+    inventory: BTreeMap<usize, usize>,
+    missions: BTreeMap<String, MissionProgress>,
+}
+
+/// A fractal along with everything happening in it.
+///
+/// `P` decides the shape of the fractal's tiles.
+/// Biome behaviors are written for triangles, so other shapes tick through `tick_with`.
 #[derive(Debug)]
-pub struct Fractory {
-    pub fractal: Fractal,
+pub struct Fractory<P = TilePos> {
+    pub fractal: Fractal<P>,
 
     /// Which tiles are activated this tick.
-    pub activated: ActiveTiles,
+    pub activated: ActiveTiles<P>,
 
     /// The player's inventory.
     /// Each index corresponds to how many of a tile the player has.
     pub inventory: BTreeMap<usize, usize>,
+
+    /// Everything stored by the Fractory since the stats were last reset.
+    pub throughput: Throughput,
 }
 
-impl Fractory {
-    /// TODO: FOR TESTING PURPOSES
-    pub fn rot_cw(&mut self) {
-        let main = self.fractal.get(TilePos::UNIT);
-
-        self.fractal.set(
-            TilePos::UNIT,
-            Tile {
-                id: main.id,
-                orient: main.orient.rot_cw(),
-            },
-        );
+/// What happened during a single tick.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickReport<P = TilePos> {
+    /// How many of each fragment were stored.
+    pub stored: BTreeMap<usize, usize>,
+    /// Where each stored fragment was stored from.
+    pub stored_from: Vec<P>,
+    /// Every move that happened, after resolving collisions.
+    pub moves: CleanMoveList<P>,
+}
+
+impl<P: Position> Fractory<P> {
+    /// Creates a Fractory with nothing activated or stored.
+    pub fn new(fractal: Fractal<P>) -> Self {
+        Self {
+            fractal,
+            activated: ActiveTiles::new(),
+            inventory: BTreeMap::new(),
+            throughput: Throughput::new(),
+        }
+    }
 
-        let mut new_set = HashSet::new();
-        for pos in &self.activated.0 {
-            let mut new_pos = TilePos::UNIT;
-            for subtile in *pos {
-                new_pos.push_back(subtile + Transform::KR);
+    /// Moves every leaf into a new fractal, such as one built after editing a fragment's composition.
+    /// Leaves whose fragment now has different symmetries are turned upright.
+    ///
+    /// Older roots, like the ones kept for undo, don't exist in the new fractal.
+    pub fn rebuild(&mut self, mut fractal: Fractal<P>) {
+        for (pos, mut tile, _) in self.fractal.leaves() {
+            let symmetries = fractal.get_info(tile.id).symmetries;
+            if tile.orient.symmetries() != symmetries {
+                tile.orient = symmetries.into();
             }
-            new_set.insert(new_pos);
+            fractal.set(pos, tile);
         }
-        self.activated.0 = new_set;
+        self.fractal = fractal;
     }
 
+    /// Rotates or reflects the whole subtree at `pos` in place,
+    /// along with every activated position inside of it.
+    pub fn transform_at(&mut self, pos: P, transform: Transform) {
+        let tile = self.fractal.get(pos);
+        self.fractal.set(pos, tile + transform);
+        self.activated.transform(pos, transform);
+    }
+
+    pub fn toggle_activation(&mut self, pos: P) {
+        self.activated.toggle(pos);
+    }
+    pub fn activate(&mut self, pos: P) {
+        self.activated.activate(pos);
+    }
+    pub fn deactivate(&mut self, pos: P) {
+        self.activated.deactivate(pos);
+    }
+
+    /// returns the id of the stored tile, if anything was stored.
+    fn _store(
+        fractal: &mut Fractal<P>,
+        inventory: &mut BTreeMap<usize, usize>,
+        pos: P,
+    ) -> Option<usize> {
+        let tile = fractal.get(pos);
+        if !fractal.get_info(tile.id).fill.is_full() {
+            return None;
+        }
+        fractal.set(pos, Tile::SPACE);
+        // let the factory pick up empty tiles for a secret achievement
+        *inventory.entry(tile.id).or_insert(0) += 1;
+        Some(tile.id)
+    }
+
+    pub fn store(&mut self, pos: P) {
+        Self::_store(&mut self.fractal, &mut self.inventory, pos);
+    }
+
+    /// Simulates 1 tick of the Fractory,
+    /// where `behavior` gives what each fragment does when it's upright.
+    ///
+    /// `tick` does this for triangles with the behaviors of a biome;
+    /// other shapes have to bring their own.
+    pub fn tick_with<'a>(
+        &mut self,
+        behavior: impl Fn(usize) -> &'a [TargetedAction<P::Offset>],
+        policy: CollisionPolicy,
+    ) -> TickReport<P> {
+        self.tick_bounded_with(behavior, policy).0
+    }
+
+    /// Like `tick_with`, but also returns whether anything tried to reach outside of the fractal.
+    fn tick_bounded_with<'a>(
+        &mut self,
+        behavior: impl Fn(usize) -> &'a [TargetedAction<P::Offset>],
+        policy: CollisionPolicy,
+    ) -> (TickReport<P>, bool) {
+        let Self {
+            fractal,
+            activated,
+            inventory,
+            throughput,
+        } = self;
+
+        // let Some(biome) = biomes.get(&biome) else {
+        //     // panic in debug mode
+        //     debug_assert!(false, "biome {biome:?} was not loaded before tick.");
+        //     return;
+        // };
+
+        let mut actions = RawMoveList::default();
+        let mut report = TickReport::<P>::default();
+        let mut escaped = false;
+
+        // tiles act in order of their paths, rather than however they happened to be stored,
+        // so a region ticks the same no matter where it is
+        let mut prev_activated = std::mem::take(activated).iter().collect::<Vec<_>>();
+        prev_activated.sort_by_cached_key(|&pos| path_of(pos));
+        for pos in prev_activated {
+            let Tile { id, orient } = fractal.get(pos);
+
+            let tile_tf = orient.transform();
+
+            for TargetedAction {
+                mut target,
+                act,
+                priority,
+            } in behavior(id).iter().copied()
+            {
+                target += tile_tf;
+                let Some(target) = pos + target else {
+                    escaped = true;
+                    continue;
+                };
+                match act {
+                    TileAction::Move(mut destination, transform) => {
+                        destination += tile_tf;
+                        let Some(destination) = pos + destination else {
+                            escaped = true;
+                            continue;
+                        };
+                        actions.add(target, destination, tile_tf * transform, priority);
+                    }
+                    TileAction::Store => {
+                        if let Some(id) = Self::_store(fractal, inventory, target) {
+                            *report.stored.entry(id).or_insert(0) += 1;
+                            report.stored_from.push(target);
+                        }
+                    }
+                    TileAction::Activate => drop(activated.activate(target)),
+                }
+            }
+        }
+        let (moves, pushed_off) = actions.apply_bounded(fractal, policy);
+        report.moves = moves;
+        throughput.record(1, &report.stored);
+        (report, escaped || pushed_off)
+    }
+}
+
+/// the subtiles leading to a position, outermost first.
+fn path_of<P: Position>(mut pos: P) -> Vec<SubTile> {
+    std::iter::from_fn(|| pos.pop_front()).collect()
+}
+
+impl Fractory {
     /// TODO: FOR TESTING PURPOSES
-    pub fn new_xyyy() -> Self {
-        let mut out = Self {
-            fractal: Fractal::new_xyyy(),
-            activated: ActiveTiles::new(),
-            inventory: BTreeMap::new(),
-        };
+    pub fn new_xyyy(fragments: &FragmentRegistry) -> Self {
+        let tile = |name| fragments.tile(name).unwrap();
+        let (x, z, w, rotor, grower, sucker, wire) = (
+            tile("X"),
+            tile("Flip-Flop"),
+            tile("Spinner"),
+            tile("Rotor"),
+            tile("Grower"),
+            tile("Sucker"),
+            tile("Wire"),
+        );
+
+        let pos = |pos: &str| pos.parse::<TilePos>().unwrap();
+        let mut out = Self::new(fragments.build_fractal().unwrap());
 
         out.fractal.set(TilePos::UNIT, Tile::SPACE);
 
@@ -196,426 +451,159 @@ impl Fractory {
         match config {
             Config::Empty => {}
             Config::TestZ => {
-                out.fractal.set(
-                    TilePos {
-                        depth: 1,
-                        pos: IVec2 { x: 0, y: 0 },
-                        flop: false,
-                    },
-                    Tile::Z,
-                );
+                out.fractal.set(pos("d1:0,0"), z);
 
-                out.fractal.set(
-                    TilePos {
-                        depth: 1,
-                        pos: IVec2 { x: 0, y: 0 },
-                        flop: true,
-                    },
-                    Tile::Z + Transform::KR,
-                );
-                out.activate(TilePos {
-                    depth: 1,
-                    pos: IVec2 { x: 0, y: 0 },
-                    flop: true,
-                });
+                out.fractal.set(pos("d1:0,0f"), z + Transform::KR);
+                out.activate(pos("d1:0,0f"));
 
-                out.fractal.set(
-                    TilePos {
-                        depth: 1,
-                        pos: IVec2 { x: 0, y: 1 },
-                        flop: false,
-                    },
-                    Tile::Z,
-                );
-                out.fractal.set(
-                    TilePos {
-                        depth: 2,
-                        pos: IVec2 { x: 0, y: 3 },
-                        flop: false,
-                    },
-                    Tile::X,
-                );
+                out.fractal.set(pos("d1:0,1"), z);
+                out.fractal.set(pos("d2:0,3"), x);
 
-                out.fractal.set(
-                    TilePos {
-                        depth: 1,
-                        pos: IVec2 { x: 1, y: 1 },
-                        flop: false,
-                    },
-                    Tile::Z,
-                );
-                out.fractal.set(
-                    TilePos {
-                        depth: 2,
-                        pos: IVec2 { x: 2, y: 3 },
-                        flop: false,
-                    },
-                    Tile::X,
-                );
+                out.fractal.set(pos("d1:1,1"), z);
+                out.fractal.set(pos("d2:2,3"), x);
             }
             Config::TestW => {
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 3, y: 5 },
-                        flop: false,
-                    },
-                    Tile::W,
-                );
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 3, y: 5 },
-                        flop: false,
-                    },
-                    Tile::SPACE,
-                );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 3, y: 5 },
-                    flop: false,
-                });
+                out.fractal.set(pos("d3:3,5"), w);
+                out.fractal.set(pos("d3:3,5"), Tile::SPACE);
+                out.activate(pos("d3:3,5"));
 
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: false,
-                    },
-                    Tile::W,
-                );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 1, y: 2 },
-                    flop: false,
-                });
+                out.fractal.set(pos("d3:1,2"), w);
+                out.activate(pos("d3:1,2"));
             }
             Config::TestRotor => {
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: false,
-                    },
-                    Tile::ROTOR,
-                );
+                out.fractal.set(pos("d3:1,2"), rotor);
 
                 out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: true,
-                    },
+                    pos("d3:1,2f"),
                     Tile {
-                        id: Tile::W.id,
-                        orient: Tile::W.orient.rot_cw(),
+                        id: w.id,
+                        orient: w.orient.rot_cw(),
                     },
                 );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 1, y: 2 },
-                    flop: true,
-                });
+                out.activate(pos("d3:1,2f"));
             }
             Config::TestGrowFarm => {
+                out.fractal.set(pos("d3:0,1"), w);
                 out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 0, y: 1 },
-                        flop: false,
-                    },
-                    Tile::W,
-                );
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 0, y: 1 },
-                        flop: true,
-                    },
+                    pos("d3:0,1f"),
                     Tile {
-                        id: Tile::WIRE.id,
-                        orient: Tile::WIRE.orient.rot_cw().rot_cw(),
+                        id: wire.id,
+                        orient: wire.orient.rot_cw().rot_cw(),
                     },
                 );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 0, y: 1 },
-                    flop: true,
-                });
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: false,
-                    },
-                    Tile::GROWER,
-                );
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: true,
-                    },
-                    Tile::X,
-                );
+                out.activate(pos("d3:0,1f"));
+                out.fractal.set(pos("d3:1,2"), grower);
+                out.fractal.set(pos("d3:1,2f"), x);
 
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 2, y: 6 },
-                        flop: false,
-                    },
+                    pos("d4:2,6"),
                     Tile {
-                        id: Tile::SUCKER.id,
-                        orient: Tile::SUCKER.orient.rot_cw().rot_cw(),
+                        id: sucker.id,
+                        orient: sucker.orient.rot_cw().rot_cw(),
                     },
                 );
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 3, y: 7 },
-                        flop: false,
-                    },
+                    pos("d4:3,7"),
                     Tile {
-                        id: Tile::SUCKER.id,
-                        orient: Tile::SUCKER.orient.rot_cw().rot_cw(),
+                        id: sucker.id,
+                        orient: sucker.orient.rot_cw().rot_cw(),
                     },
                 );
+                out.fractal.set(pos("d4:2,7"), wire);
+                out.activate(pos("d4:2,7"));
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 2, y: 7 },
-                        flop: false,
-                    },
-                    Tile::WIRE,
-                );
-                out.activate(TilePos {
-                    depth: 4,
-                    pos: IVec2 { x: 2, y: 7 },
-                    flop: false,
-                });
-                out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 1, y: 6 },
-                        flop: true,
-                    },
+                    pos("d4:1,6f"),
                     Tile {
-                        id: Tile::W.id,
-                        orient: Tile::W.orient.rot_cw(),
+                        id: w.id,
+                        orient: w.orient.rot_cw(),
                     },
                 );
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 2, y: 6 },
-                        flop: true,
-                    },
+                    pos("d4:2,6f"),
                     Tile {
-                        id: Tile::WIRE.id,
-                        orient: Tile::WIRE.orient.rot_cw().rot_cw(),
+                        id: wire.id,
+                        orient: wire.orient.rot_cw().rot_cw(),
                     },
                 );
 
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 4, y: 6 },
-                        flop: false,
-                    },
+                    pos("d4:4,6"),
                     Tile {
-                        id: Tile::SUCKER.id,
-                        orient: Tile::SUCKER.orient.rot_cw(),
+                        id: sucker.id,
+                        orient: sucker.orient.rot_cw(),
                     },
                 );
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 4, y: 7 },
-                        flop: false,
-                    },
+                    pos("d4:4,7"),
                     Tile {
-                        id: Tile::W.id,
-                        orient: Tile::W.orient.rot_cw().rot_cw(),
+                        id: w.id,
+                        orient: w.orient.rot_cw().rot_cw(),
                     },
                 );
-                out.activate(TilePos {
-                    depth: 4,
-                    pos: IVec2 { x: 4, y: 7 },
-                    flop: false,
-                });
+                out.activate(pos("d4:4,7"));
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 4, y: 6 },
-                        flop: true,
-                    },
+                    pos("d4:4,6f"),
                     Tile {
-                        id: Tile::WIRE.id,
-                        orient: Tile::WIRE.orient.rot_cw(),
+                        id: wire.id,
+                        orient: wire.orient.rot_cw(),
                     },
                 );
             }
             Config::TestGrowBug => {
                 out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 1, y: 2 },
-                        flop: false,
-                    },
+                    pos("d3:1,2"),
                     Tile {
-                        id: Tile::GROWER.id,
-                        orient: Tile::GROWER.orient.rot_cw(),
+                        id: grower.id,
+                        orient: grower.orient.rot_cw(),
                     },
                 );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 1, y: 2 },
-                    flop: false,
-                });
-                out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 1, y: 4 },
-                        flop: false,
-                    },
-                    Tile::X,
-                );
+                out.activate(pos("d3:1,2"));
+                out.fractal.set(pos("d4:1,4"), x);
 
-                out.fractal.set(
-                    TilePos {
-                        depth: 3,
-                        pos: IVec2 { x: 2, y: 2 },
-                        flop: false,
-                    },
-                    Tile::GROWER,
-                );
-                out.activate(TilePos {
-                    depth: 3,
-                    pos: IVec2 { x: 2, y: 2 },
-                    flop: false,
-                });
-                out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 5, y: 6 },
-                        flop: false,
-                    },
-                    Tile::X,
-                );
+                out.fractal.set(pos("d3:2,2"), grower);
+                out.activate(pos("d3:2,2"));
+                out.fractal.set(pos("d4:5,6"), x);
             }
             Config::TestActiveBug => {
-                out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 2, y: 5 },
-                        flop: true,
-                    },
-                    Tile::W,
-                );
-                out.activate(TilePos {
-                    depth: 4,
-                    pos: IVec2 { x: 2, y: 5 },
-                    flop: true,
-                });
+                out.fractal.set(pos("d4:2,5f"), w);
+                out.activate(pos("d4:2,5f"));
 
                 out.fractal.set(
-                    TilePos {
-                        depth: 4,
-                        pos: IVec2 { x: 3, y: 5 },
-                        flop: true,
-                    },
+                    pos("d4:3,5f"),
                     Tile {
-                        id: Tile::W.id,
-                        orient: Tile::W.orient.flip(),
+                        id: w.id,
+                        orient: w.orient.flip(),
                     },
                 );
-                out.activate(TilePos {
-                    depth: 4,
-                    pos: IVec2 { x: 3, y: 5 },
-                    flop: true,
-                });
+                out.activate(pos("d4:3,5f"));
             }
         }
 
         out
     }
 
-    pub fn toggle_activation(&mut self, pos: TilePos) {
-        self.activated.toggle(pos);
-    }
-    pub fn activate(&mut self, pos: TilePos) {
-        self.activated.activate(pos);
-    }
-    pub fn deactivate(&mut self, pos: TilePos) {
-        self.activated.deactivate(pos);
-    }
-
-    fn _store(fractal: &mut Fractal, inventory: &mut BTreeMap<usize, usize>, pos: TilePos) {
-        let tile = fractal.get(pos);
-        if !fractal.get_info(tile.id).fill.is_full() {
-            return;
-        }
-        fractal.set(pos, Tile::SPACE);
-        // let the factory pick up empty tiles for a secret achievement
-        *inventory.entry(tile.id).or_insert(0) += 1;
+    /// Simulates 1 tick of the Fractory.
+    pub fn tick(&mut self, fragments: &FragmentRegistry, biome: &Biome) -> TickReport {
+        self.tick_bounded(fragments, biome).0
     }
 
-    pub fn store(&mut self, pos: TilePos) {
-        Self::_store(&mut self.fractal, &mut self.inventory, pos)
+    /// Like `tick`, but also returns whether anything tried to reach outside of the fractal.
+    fn tick_bounded(&mut self, fragments: &FragmentRegistry, biome: &Biome) -> (TickReport, bool) {
+        self.tick_bounded_with(
+            |id| biome.behavior(fragments, id).as_slice(),
+            biome.collision_policy(),
+        )
     }
 
-    /// Simulates 1 tick of the Fractory.
-    pub fn tick(&mut self, behaviors: &[Behavior], filter: &Filter) {
-        // TODO: move poc-fractal/src/tree.rs and poc-fractal/src/tree/collision.rs
-        // to be under common/src/sim/logic/actions.rs
-        // and finish RawMoveList::apply();
-
-        let Self {
-            fractal,
-            activated: ActiveTiles(activated),
-            inventory,
-        } = self;
-
-        // let Some(biome) = biomes.get(&biome) else {
-        //     // panic in debug mode
-        //     debug_assert!(false, "biome {biome:?} was not loaded before tick.");
-        //     return;
-        // };
-
-        let mut actions = RawMoveList::default();
-
-        let prev_activated = std::mem::take(activated);
-        for pos in prev_activated {
-            let Tile { id, orient } = fractal.get(pos);
-
-            let tile_tf = orient.transform();
-            let Some(behaviors) = filter
-                .allows(id)
-                .then_some(())
-                .and_then(|_| behaviors.get(id))
-            else {
-                continue;
-            };
-
-            for TargetedAction { mut target, act } in behaviors.iter().copied() {
-                target += tile_tf;
-                let Some(target) = pos + target else {
-                    continue;
-                };
-                match act {
-                    TileAction::Move(mut destination, transform) => {
-                        destination += tile_tf;
-                        let Some(destination) = pos + destination else {
-                            continue;
-                        };
-                        actions.add(target, destination, tile_tf * transform);
-                    }
-                    TileAction::Store => Self::_store(fractal, inventory, target),
-                    TileAction::Activate => drop(self.activated.activate(target)),
-                }
-            }
-        }
-        let _actions = actions.apply(&mut self.fractal);
+    /// Simulates many ticks of the Fractory at once,
+    /// reusing whatever `memo` remembers from previous runs.
+    pub fn tick_many(
+        &mut self,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        memo: &mut TickMemo,
+        ticks: u64,
+    ) {
+        memo.advance(self, fragments, biome, ticks);
     }
 }
@@ -0,0 +1,229 @@
+#[cfg(test)]
+mod tests;
+
+use crate::sim::logic::{
+    orientation::Transform,
+    path::{Position, TilePos},
+    tile::{Quad, SubTile},
+};
+use std::{marker::PhantomData, ops::AddAssign};
+
+/// a set of activated positions, stored as a quadtree aligned with the fractal.
+///
+/// whole subtrees can be transformed or moved in O(depth),
+/// since transforms are only pushed down to the children once something below them changes.
+#[derive(Debug, Clone)]
+pub struct ActiveTiles<P = TilePos>(Node, PhantomData<P>);
+
+impl<P: Position> Default for ActiveTiles<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Position> ActiveTiles<P> {
+    pub fn new() -> Self {
+        Self(Node::EMPTY, PhantomData)
+    }
+
+    pub fn contains(&self, pos: P) -> bool {
+        self.0.get(pos).is_some_and(|node| node.active)
+    }
+
+    /// checks if a position, or anything inside of it, is active.
+    pub fn any_under(&self, pos: P) -> bool {
+        self.0.get(pos).is_some_and(|node| !node.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// how many positions are active.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// activates a position.
+    ///
+    /// returns true if the position used to be inactive.
+    pub fn activate(&mut self, pos: P) -> bool {
+        self.0
+            .update(pos, |node| !std::mem::replace(&mut node.active, true))
+    }
+
+    /// deactivates a position.
+    ///
+    /// returns true if the position used to be active.
+    pub fn deactivate(&mut self, pos: P) -> bool {
+        self.0
+            .update(pos, |node| std::mem::replace(&mut node.active, false))
+    }
+
+    /// toggles whether a position is active or inactive.
+    ///
+    /// returns true if the position is now active.
+    pub fn toggle(&mut self, pos: P) -> bool {
+        self.0.update(pos, |node| {
+            node.active ^= true;
+            node.active
+        })
+    }
+
+    /// transforms every active position under `pos`,
+    /// the same way the tile at `pos` would be transformed.
+    ///
+    /// `pos` itself stays where it is.
+    pub fn transform(&mut self, pos: P, transform: Transform) {
+        self.0.update(pos, |node| *node += transform);
+    }
+
+    /// removes every active position under `pos`, including `pos` itself,
+    /// and returns them relative to `pos`.
+    pub fn take_subtree(&mut self, pos: P) -> Self {
+        Self(
+            self.0
+                .update(pos, |node| std::mem::replace(node, Node::EMPTY)),
+            PhantomData,
+        )
+    }
+
+    /// replaces every active position under `pos` with `subtree`, placed relative to `pos`.
+    ///
+    /// returns the positions that were replaced, relative to `pos`.
+    pub fn set_subtree(&mut self, pos: P, subtree: Self) -> Self {
+        Self(
+            self.0
+                .update(pos, |node| std::mem::replace(node, subtree.0)),
+            PhantomData,
+        )
+    }
+
+    /// moves every active position under `from` to be under `to` instead,
+    /// replacing whatever was there.
+    pub fn move_subtree(&mut self, from: P, to: P) {
+        let subtree = self.take_subtree(from);
+        self.set_subtree(to, subtree);
+    }
+
+    /// iterates over every active position, outermost first.
+    pub fn iter(&self) -> Iter<'_, P> {
+        Iter {
+            stack: vec![(&self.0, P::UNIT, Transform::KU)],
+        }
+    }
+}
+
+impl<P: Position> FromIterator<P> for ActiveTiles<P> {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        let mut out = Self::new();
+        out.extend(iter);
+        out
+    }
+}
+
+impl<P: Position> Extend<P> for ActiveTiles<P> {
+    fn extend<T: IntoIterator<Item = P>>(&mut self, iter: T) {
+        for pos in iter {
+            self.activate(pos);
+        }
+    }
+}
+
+impl<'a, P: Position> IntoIterator for &'a ActiveTiles<P> {
+    type Item = P;
+    type IntoIter = Iter<'a, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, P = TilePos> {
+    /// each node, along with its position and the transforms of all its ancestors
+    stack: Vec<(&'a Node, P, Transform)>,
+}
+
+impl<P: Position> Iterator for Iter<'_, P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, pos, transform)) = self.stack.pop() {
+            if let Some(children) = &node.children {
+                let transform = node.pending + transform;
+                for subtile in SubTile::QUAD.0.into_iter().rev() {
+                    let mut child_pos = pos;
+                    child_pos.push_back(subtile + transform);
+                    self.stack.push((&children[subtile], child_pos, transform));
+                }
+            }
+            if node.active {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    active: bool,
+    /// a transform that still has to be applied to the children
+    pending: Transform,
+    children: Option<Box<Quad<Self>>>,
+}
+
+impl Node {
+    const EMPTY: Self = Self {
+        active: false,
+        pending: Transform::KU,
+        children: None,
+    };
+
+    fn is_empty(&self) -> bool {
+        !self.active && self.children.is_none()
+    }
+
+    fn get(&self, pos: impl Position) -> Option<&Self> {
+        let mut node = self;
+        let mut transform = Transform::KU;
+        for subtile in pos {
+            transform = node.pending + transform;
+            node = &node.children.as_ref()?[subtile - transform];
+        }
+        Some(node)
+    }
+
+    fn child_mut(&mut self, subtile: SubTile) -> &mut Self {
+        if let Some(children) = &mut self.children {
+            **children += self.pending;
+        }
+        self.pending = Transform::KU;
+        let children = self
+            .children
+            .get_or_insert_with(|| Box::new(Quad([Self::EMPTY; 4])));
+        &mut children[subtile]
+    }
+
+    /// runs `f` on the node at `pos`, then cleans up any empty branches along the way.
+    fn update<T>(&mut self, mut pos: impl Position, f: impl FnOnce(&mut Self) -> T) -> T {
+        let Some(subtile) = pos.pop_front() else {
+            return f(self);
+        };
+        let out = self.child_mut(subtile).update(pos, f);
+        if self
+            .children
+            .as_ref()
+            .is_some_and(|children| children.0.iter().all(Self::is_empty))
+        {
+            self.children = None;
+        }
+        out
+    }
+}
+
+impl AddAssign<Transform> for Node {
+    fn add_assign(&mut self, rhs: Transform) {
+        self.pending = self.pending + rhs;
+    }
+}
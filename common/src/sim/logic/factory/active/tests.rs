@@ -0,0 +1,158 @@
+use super::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::collections::HashSet;
+
+use SubTile::*;
+
+fn path(subtiles: &[SubTile]) -> TilePos {
+    TilePos::from_inward_path(subtiles.iter().copied())
+}
+
+fn positions(active: &ActiveTiles) -> HashSet<TilePos> {
+    active.iter().collect()
+}
+
+#[test]
+fn test_activate() {
+    let mut active = ActiveTiles::new();
+    assert!(active.is_empty());
+    assert!(active.activate(path(&[U, L])));
+    assert!(!active.activate(path(&[U, L])));
+    assert!(active.toggle(path(&[U])));
+    assert_eq!(active.len(), 2);
+
+    assert!(active.contains(path(&[U])));
+    assert!(active.contains(path(&[U, L])));
+    assert!(!active.contains(path(&[U, R])));
+    assert!(!active.contains(TilePos::UNIT));
+
+    assert!(active.deactivate(path(&[U, L])));
+    assert!(!active.deactivate(path(&[U, L])));
+    assert!(!active.toggle(path(&[U])));
+    assert!(active.is_empty());
+}
+
+#[test]
+fn test_any_under() {
+    let active = ActiveTiles::from_iter([path(&[R, C, L])]);
+    assert!(active.any_under(TilePos::UNIT));
+    assert!(active.any_under(path(&[R])));
+    assert!(active.any_under(path(&[R, C])));
+    assert!(active.any_under(path(&[R, C, L])));
+    assert!(!active.any_under(path(&[R, C, L, C])));
+    assert!(!active.any_under(path(&[R, U])));
+    assert!(!active.any_under(path(&[L])));
+}
+
+#[test]
+fn test_transform() {
+    let mut active = ActiveTiles::from_iter([path(&[U]), path(&[U, R]), path(&[C, L])]);
+    active.transform(path(&[U]), Transform::KR);
+    assert_eq!(
+        positions(&active),
+        HashSet::from([path(&[U]), path(&[U, L]), path(&[C, L])])
+    );
+    active.transform(TilePos::UNIT, Transform::FU);
+    assert_eq!(
+        positions(&active),
+        HashSet::from([path(&[U]), path(&[U, R]), path(&[C, R])])
+    );
+}
+
+#[test]
+fn test_move_subtree() {
+    let mut active = ActiveTiles::from_iter([path(&[U]), path(&[U, R, C]), path(&[L, L])]);
+    active.transform(path(&[U]), Transform::KL);
+    active.move_subtree(path(&[U]), path(&[L]));
+    assert_eq!(
+        positions(&active),
+        HashSet::from([path(&[L]), path(&[L, U, C])])
+    );
+}
+
+/// applies `transform` to every path starting with `prefix`, leaving the prefix alone
+fn transform_flat(
+    set: &HashSet<TilePos>,
+    prefix: &[SubTile],
+    transform: Transform,
+) -> HashSet<TilePos> {
+    set.iter()
+        .map(|pos| {
+            let subtiles = pos.collect::<Vec<_>>();
+            if !subtiles.starts_with(prefix) {
+                return *pos;
+            }
+            let (outer, inner) = subtiles.split_at(prefix.len());
+            path(
+                &outer
+                    .iter()
+                    .copied()
+                    .chain(inner.iter().map(|subtile| *subtile + transform))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn fuzz_against_hashset() {
+    let mut rng = StdRng::seed_from_u64(0xAC71E);
+    let random_path = |rng: &mut StdRng| {
+        let depth = rng.gen_range(0..4);
+        (0..depth)
+            .map(|_| *SubTile::QUAD.0.choose(rng).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let mut active = ActiveTiles::new();
+    let mut flat = HashSet::new();
+    for _ in 0..1 << 12 {
+        let subtiles = random_path(&mut rng);
+        let pos = path(&subtiles);
+        match rng.gen_range(0..5) {
+            0 => assert_eq!(active.activate(pos), flat.insert(pos)),
+            1 => assert_eq!(active.deactivate(pos), flat.remove(&pos)),
+            2 => {
+                let transform = *Transform::TRANSFORMS.choose(&mut rng).unwrap();
+                active.transform(pos, transform);
+                flat = transform_flat(&flat, &subtiles, transform);
+            }
+            3 => {
+                let to_subtiles = random_path(&mut rng);
+                let to = path(&to_subtiles);
+                let (taken, kept) = flat
+                    .iter()
+                    .partition::<Vec<TilePos>, _>(|p| p.collect::<Vec<_>>().starts_with(&subtiles));
+                let moved = taken
+                    .iter()
+                    .map(|p| {
+                        path(&[&to_subtiles, &p.collect::<Vec<_>>()[subtiles.len()..]].concat())
+                    })
+                    .collect::<Vec<_>>();
+                // keep the tree from growing too deep
+                if moved.iter().any(|p| p.depth() > 12) {
+                    continue;
+                }
+                active.move_subtree(pos, to);
+                flat = kept
+                    .into_iter()
+                    .filter(|p| !p.collect::<Vec<_>>().starts_with(&to_subtiles))
+                    .chain(moved)
+                    .collect();
+            }
+            _ => {
+                for query in [pos, path(&subtiles[..subtiles.len().saturating_sub(1)])] {
+                    assert_eq!(active.contains(query), flat.contains(&query));
+                    let query_path = query.collect::<Vec<_>>();
+                    assert_eq!(
+                        active.any_under(query),
+                        flat.iter()
+                            .any(|pos| pos.collect::<Vec<_>>().starts_with(&query_path))
+                    );
+                }
+            }
+        }
+        assert_eq!(positions(&active), flat);
+        assert_eq!(active.len(), flat.len());
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests;
+
+use super::Fractory;
+use crate::sim::logic::{
+    fragment::FragmentRegistry,
+    path::TilePos,
+    planet::Biome,
+    tile::{Quad, SubTile, Tile},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// activated positions relative to some tile,
+/// kept sorted so equal sets hash the same.
+type Active = Vec<TilePos>;
+
+/// the result of advancing a tile and its activations by some number of ticks.
+#[derive(Debug, Clone)]
+struct Outcome {
+    tile: Tile,
+    active: Active,
+    /// every item stored along the way
+    stored: BTreeMap<usize, usize>,
+    /// whether every action stayed inside of the tile.
+    /// only contained outcomes hold wherever the tile is placed.
+    contained: bool,
+}
+
+/// memoized results of advancing subtrees by powers of 2 ticks.
+///
+/// a subtree is identified by its tile and the activations inside of it.
+/// the fractal is hash-consed, so the tile identifies the whole subtree,
+/// and activations are relative, so the same region is shared wherever it appears.
+///
+/// unlike hashlife, effects aren't bounded by a fixed speed of light,
+/// since a single push or chain of moves can cross the whole fractal.
+/// instead, each outcome records whether it stayed inside of its tile,
+/// and a parent only reuses its children's outcomes when all of them did.
+///
+/// a memo is only valid for the fractal, fragments and biome it was filled with.
+#[derive(Debug, Default)]
+pub struct TickMemo {
+    /// `levels[k]` maps a subtree to where it ends up after `2^k` ticks
+    levels: Vec<HashMap<(Tile, Active), Outcome>>,
+}
+
+impl TickMemo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// forgets every cached subtree.
+    /// must be called when switching to a different fractal, set of fragments, or biome.
+    pub fn clear(&mut self) {
+        self.levels.clear();
+    }
+
+    /// how many outcomes are cached, across all levels.
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(super) fn advance(
+        &mut self,
+        fractory: &mut Fractory,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        ticks: u64,
+    ) {
+        let mut tile = fractory.fractal.root;
+        let mut active = sorted(fractory.activated.iter().collect());
+        let mut sim = Sim {
+            fractory,
+            fragments,
+            biome,
+        };
+        for level in (0..u64::BITS as usize).rev() {
+            if ticks >> level & 1 == 0 {
+                continue;
+            }
+            // the root has nowhere else to go, so it doesn't need to be contained
+            let outcome = self.outcome(&mut sim, level, tile, active);
+            add_items(&mut sim.fractory.inventory, &outcome.stored);
+            sim.fractory.throughput.record(1 << level, &outcome.stored);
+            tile = outcome.tile;
+            active = outcome.active;
+        }
+        load(sim.fractory, tile, &active);
+    }
+
+    /// finds where a subtree ends up after `2^level` ticks.
+    fn outcome(&mut self, sim: &mut Sim, level: usize, tile: Tile, active: Active) -> Outcome {
+        if active.is_empty() {
+            return Outcome {
+                tile,
+                active,
+                stored: BTreeMap::new(),
+                contained: true,
+            };
+        }
+        let key = (tile, active);
+        if let Some(outcome) = self.levels.get(level).and_then(|memo| memo.get(&key)) {
+            return outcome.clone();
+        }
+        let (tile, active) = key;
+
+        let outcome = match self.outcome_of_children(sim, level, tile, &active) {
+            Some(outcome) => outcome,
+            None if level == 0 => sim.tick(tile, &active),
+            None => {
+                let first = self.outcome(sim, level - 1, tile, active.clone());
+                let mut second = self.outcome(sim, level - 1, first.tile, first.active);
+                add_items(&mut second.stored, &first.stored);
+                second.contained &= first.contained;
+                second
+            }
+        };
+
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, HashMap::new);
+        }
+        self.levels[level].insert((tile, active), outcome.clone());
+        outcome
+    }
+
+    /// advances each child of a tile on its own, then puts them back together.
+    /// returns None if the tile itself is active or any child reaches outside of itself.
+    fn outcome_of_children(
+        &mut self,
+        sim: &mut Sim,
+        level: usize,
+        tile: Tile,
+        active: &Active,
+    ) -> Option<Outcome> {
+        let fractal = &sim.fractory.fractal;
+        if fractal.library[tile.id].fill.is_leaf() || active.contains(&TilePos::UNIT) {
+            return None;
+        }
+        let mut quad = fractal.library[tile.id].quad;
+        quad += tile.orient.transform();
+
+        let mut parts = Quad([vec![], vec![], vec![], vec![]]);
+        for &pos in active {
+            let mut pos = pos;
+            let subtile = pos.pop_front()?;
+            parts[subtile].push(pos);
+        }
+
+        let mut stored = BTreeMap::new();
+        let mut active = vec![];
+        for subtile in SubTile::QUAD.0 {
+            let part = std::mem::take(&mut parts[subtile]);
+            let child = self.outcome(sim, level, quad[subtile], sorted(part));
+            if !child.contained {
+                return None;
+            }
+            quad[subtile] = child.tile;
+            add_items(&mut stored, &child.stored);
+            active.extend(child.active.into_iter().map(|mut pos| {
+                pos.push_front(subtile);
+                pos
+            }));
+        }
+
+        Some(Outcome {
+            tile: sim.fractory.fractal.register(quad),
+            active: sorted(active),
+            stored,
+            contained: true,
+        })
+    }
+}
+
+/// everything needed to actually simulate a tick.
+struct Sim<'a> {
+    fractory: &'a mut Fractory,
+    fragments: &'a FragmentRegistry,
+    biome: &'a Biome,
+}
+
+impl Sim<'_> {
+    /// simulates 1 tick of a subtree as if it were the whole fractal.
+    fn tick(&mut self, tile: Tile, active: &Active) -> Outcome {
+        let fractory = &mut *self.fractory;
+        load(fractory, tile, active);
+        let inventory = std::mem::take(&mut fractory.inventory);
+        let throughput = std::mem::take(&mut fractory.throughput);
+        let (report, escaped) = fractory.tick_bounded(self.fragments, self.biome);
+        fractory.inventory = inventory;
+        fractory.throughput = throughput;
+        Outcome {
+            tile: fractory.fractal.root,
+            active: sorted(fractory.activated.iter().collect()),
+            stored: report.stored,
+            contained: !escaped,
+        }
+    }
+}
+
+fn sorted(mut active: Active) -> Active {
+    active.sort_unstable_by_key(|pos| (pos.depth, pos.pos.x, pos.pos.y, pos.flop));
+    active
+}
+
+fn load(fractory: &mut Fractory, root: Tile, active: &Active) {
+    fractory.fractal.root = root;
+    fractory.activated = active.iter().copied().collect();
+}
+
+fn add_items(inventory: &mut BTreeMap<usize, usize>, items: &BTreeMap<usize, usize>) {
+    for (&id, &count) in items {
+        *inventory.entry(id).or_insert(0) += count;
+    }
+}
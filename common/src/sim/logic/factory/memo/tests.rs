@@ -0,0 +1,120 @@
+use super::*;
+use crate::sim::logic::planet::{BiomeId, Planet};
+
+type State = (Tile, Active);
+
+fn state_of(fractory: &Fractory) -> State {
+    (
+        fractory.fractal.root,
+        sorted(fractory.activated.iter().collect()),
+    )
+}
+
+/// checks `tick_many` against the same number of single ticks,
+/// starting from `start` in each biome.
+fn check(start: impl Fn(&mut Fractory), ticks: u64) {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    for biome_id in ["Spinless", "Landing Zone", "Bulldozer"] {
+        let biome = planet.biomes().get(&BiomeId::from(biome_id)).unwrap();
+        let mut fractory = Fractory::new_xyyy(fragments);
+        start(&mut fractory);
+        let (root, active) = state_of(&fractory);
+
+        let mut memo = TickMemo::new();
+        fractory.tick_many(fragments, biome, &mut memo, ticks);
+        let memoized = (
+            state_of(&fractory),
+            std::mem::take(&mut fractory.inventory),
+            std::mem::take(&mut fractory.throughput),
+        );
+
+        // both runs share a library, so equal trees have equal root tiles
+        load(&mut fractory, root, &active);
+        for _ in 0..ticks {
+            fractory.tick(fragments, biome);
+        }
+        let ticked = (state_of(&fractory), fractory.inventory, fractory.throughput);
+
+        assert_eq!(memoized, ticked, "{ticks} ticks in {biome_id}");
+    }
+}
+
+/// moves the whole factory into `subtile` of an otherwise empty fractal.
+fn shrink_into(subtile: SubTile) -> impl Fn(&mut Fractory) {
+    move |fractory| {
+        let (root, active) = state_of(fractory);
+        fractory.fractal.root = Tile::SPACE;
+        fractory
+            .fractal
+            .set(TilePos::from_inward_path([subtile]), root);
+        fractory.activated = active
+            .into_iter()
+            .map(|mut pos| {
+                pos.push_front(subtile);
+                pos
+            })
+            .collect();
+    }
+}
+
+#[test]
+fn test_matches_tick() {
+    for ticks in [0, 1, 2, 5, 16, 37, 300] {
+        check(|_| {}, ticks);
+    }
+}
+
+#[test]
+fn test_matches_tick_in_subtile() {
+    for subtile in SubTile::QUAD.0 {
+        for ticks in [1, 5, 37] {
+            check(shrink_into(subtile), ticks);
+        }
+    }
+}
+
+#[test]
+fn test_long_run() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&BiomeId::from("Landing Zone")).unwrap();
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let (root, active) = state_of(&fractory);
+
+    // the grow farm is periodic, so only a handful of subtrees ever get cached
+    let mut memo = TickMemo::new();
+    fractory.tick_many(fragments, biome, &mut memo, 1 << 40);
+    assert!(memo.len() < 1 << 10);
+    let once = (state_of(&fractory), std::mem::take(&mut fractory.inventory));
+    assert!(once.1.values().sum::<usize>() >= 1 << 40);
+
+    // the same run split up differently
+    load(&mut fractory, root, &active);
+    for ticks in [1, (1 << 39) - 3, 2, 1 << 39] {
+        fractory.tick_many(fragments, biome, &mut memo, ticks);
+    }
+    let split = (state_of(&fractory), fractory.inventory);
+    assert_eq!(once, split);
+}
+
+#[test]
+fn test_shared_between_positions() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&BiomeId::from("Spinless")).unwrap();
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let (root, active) = state_of(&fractory);
+    shrink_into(SubTile::U)(&mut fractory);
+
+    let mut memo = TickMemo::new();
+    fractory.tick_many(fragments, biome, &mut memo, 64);
+    let cached = memo.len();
+
+    // the same factory somewhere else reuses its subtree,
+    // so at most one new root per level needs to be worked out
+    load(&mut fractory, root, &active);
+    shrink_into(SubTile::R)(&mut fractory);
+    fractory.tick_many(fragments, biome, &mut memo, 64);
+    assert!(memo.len() - cached <= 7);
+}
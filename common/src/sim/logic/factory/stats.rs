@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+/// running totals of everything a fractory has stored,
+/// for measuring how fast a production chain is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Throughput {
+    ticks: u64,
+    stored: BTreeMap<usize, u64>,
+}
+
+impl Throughput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the items stored over some number of ticks.
+    pub fn record(&mut self, ticks: u64, stored: &BTreeMap<usize, usize>) {
+        self.ticks += ticks;
+        for (&id, &count) in stored {
+            *self.stored.entry(id).or_insert(0) += count as u64;
+        }
+    }
+
+    /// how many ticks have been recorded.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// how many of a fragment have been stored in total.
+    pub fn total(&self, id: usize) -> u64 {
+        self.stored.get(&id).copied().unwrap_or(0)
+    }
+
+    /// the average number of a fragment stored per tick.
+    pub fn rate(&self, id: usize) -> f64 {
+        if self.ticks == 0 {
+            return 0.0;
+        }
+        self.total(id) as f64 / self.ticks as f64
+    }
+
+    /// the average number of each stored fragment per tick.
+    pub fn rates(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.stored.keys().map(|&id| (id, self.rate(id)))
+    }
+
+    /// forgets everything recorded so far.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
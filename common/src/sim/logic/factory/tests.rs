@@ -0,0 +1,264 @@
+use super::*;
+use crate::sim::logic::tile::{
+    Quad,
+    SubTile::{self, *},
+};
+
+fn path(subtiles: &[SubTile]) -> TilePos {
+    TilePos::from_inward_path(subtiles.iter().copied())
+}
+
+/// every path up to `depth` subtiles long
+fn paths(depth: usize) -> Vec<Vec<SubTile>> {
+    let mut out = vec![vec![]];
+    let mut layer = vec![vec![]];
+    for _ in 0..depth {
+        layer = layer
+            .iter()
+            .flat_map(|path: &Vec<SubTile>| {
+                SubTile::QUAD
+                    .0
+                    .map(|subtile| [path.as_slice(), &[subtile]].concat())
+            })
+            .collect();
+        out.extend(layer.iter().cloned());
+    }
+    out
+}
+
+#[test]
+fn test_transform_at() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let outer = [C, L, U];
+    let inner = paths(4);
+
+    for transform in Transform::TRANSFORMS {
+        let mut fractory = Fractory::new_xyyy(fragments);
+        fractory
+            .fractal
+            .set(path(&outer), fragments.tile("Rotor").unwrap());
+        fractory.activate(path(&[&outer[..], &[R, U]].concat()));
+        fractory.activate(path(&outer));
+        let under = |subtiles: &[SubTile]| path(&[&outer[..], subtiles].concat());
+        let before = inner
+            .iter()
+            .map(|subtiles| {
+                let pos = under(subtiles);
+                (fractory.fractal.get(pos), fractory.activated.contains(pos))
+            })
+            .collect::<Vec<_>>();
+
+        fractory.transform_at(path(&outer), transform);
+
+        for (subtiles, (tile, active)) in inner.iter().zip(before) {
+            let moved = subtiles
+                .iter()
+                .map(|subtile| *subtile + transform)
+                .collect::<Vec<_>>();
+            let pos = under(&moved);
+            assert_eq!(fractory.fractal.get(pos), tile + transform, "{subtiles:?}");
+            assert_eq!(fractory.activated.contains(pos), active, "{subtiles:?}");
+        }
+    }
+}
+
+#[test]
+fn test_throughput() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&BiomeId::from("Landing Zone")).unwrap();
+    let mut fractory = Fractory::new_xyyy(fragments);
+
+    let mut stored = BTreeMap::<usize, usize>::new();
+    for _ in 0..100 {
+        for (id, count) in fractory.tick(fragments, biome).stored {
+            *stored.entry(id).or_insert(0) += count;
+        }
+    }
+    assert!(!stored.is_empty());
+    assert_eq!(stored, fractory.inventory);
+    assert_eq!(fractory.throughput.ticks(), 100);
+    for (id, count) in stored {
+        assert_eq!(fractory.throughput.total(id), count as u64);
+        assert_eq!(fractory.throughput.rate(id), count as f64 / 100.0);
+    }
+
+    fractory.throughput.reset();
+    assert_eq!(fractory.throughput.rates().count(), 0);
+}
+
+#[test]
+fn test_tick_report() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&BiomeId::from("Landing Zone")).unwrap();
+    let mut fractory = Fractory::new_xyyy(fragments);
+
+    let (mut moves, mut stores) = (0, 0);
+    for _ in 0..20 {
+        let before = fractory.fractal.root;
+        let report = fractory.tick(fragments, biome);
+        for (src, dst, transform) in report.moves.iter() {
+            let tile = fractory.fractal.get_in(before, src);
+            assert_eq!(fractory.fractal.get(dst), tile + transform);
+        }
+        for &pos in &report.stored_from {
+            assert_ne!(fractory.fractal.get_in(before, pos), Tile::SPACE);
+        }
+        assert_eq!(
+            report.stored_from.len(),
+            report.stored.values().sum::<usize>()
+        );
+        moves += report.moves.len();
+        stores += report.stored_from.len();
+    }
+    assert!(moves > 0);
+    assert!(stores > 0);
+}
+
+#[test]
+fn test_rebuild() {
+    let planet = Planet::new_xyyy();
+    let mut fragments = planet.fragments().clone();
+    let mut fractory = Fractory::new_xyyy(&fragments);
+    let leaves = |fractory: &Fractory| {
+        fractory
+            .fractal
+            .leaves()
+            .map(|(pos, tile, _)| (pos, tile.id))
+            .collect::<Vec<_>>()
+    };
+    let before = leaves(&fractory);
+
+    let [x, y] = ["X", "Y"].map(|name| fragments.tile(name).unwrap());
+    let wire = fragments.id("Wire").unwrap();
+    fragments.set_composition(wire, Quad([y, x, y, y])).unwrap();
+    fractory.rebuild(fragments.build_fractal().unwrap());
+
+    // the same fragments are in the same places, but wires are made of something else now
+    assert_eq!(leaves(&fractory), before);
+    let wire = fragments.tile("Wire").unwrap();
+    let composition = SubTile::QUAD.map(|subtile| fractory.fractal.get_in(wire, path(&[subtile])));
+    assert_eq!(composition, Quad([y, x, y, y]));
+}
+
+#[test]
+fn test_save_load() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    meta.biome = BiomeId::from("Bulldozer");
+    let planet = planets.get(&meta.planet).unwrap();
+    let (fragments, biome) = (
+        planet.fragments(),
+        planet.biomes().get(&meta.biome).unwrap(),
+    );
+    for _ in 0..5 {
+        meta.tick(fragments, biome);
+    }
+    let file = std::env::temp_dir().join(format!("fractory-save-{}.ron", std::process::id()));
+    meta.save(&file).unwrap();
+    let mut loaded = FractoryMeta::load(&file, &planets).unwrap();
+    std::fs::remove_file(&file).unwrap();
+
+    let state = |meta: &FractoryMeta| {
+        let fractory = &meta.fractory;
+        let leaves = fractory.fractal.leaves().collect::<Vec<_>>();
+        let activated = fractory.activated.iter().collect::<Vec<_>>();
+        let missions = meta
+            .missions
+            .iter()
+            .map(|(name, progress)| (name.clone(), progress.goals().to_vec()))
+            .collect::<Vec<_>>();
+        (leaves, activated, fractory.inventory.clone(), missions)
+    };
+    assert_eq!(loaded.biome, meta.biome);
+    assert_eq!(state(&loaded), state(&meta));
+    assert_eq!(loaded.missions["Perpetual Motion"].goals()[0].progress, 1);
+
+    // and both carry on the same way
+    for _ in 0..5 {
+        meta.tick(fragments, biome);
+        loaded.tick(fragments, biome);
+    }
+    assert_eq!(state(&loaded), state(&meta));
+}
+
+#[test]
+fn test_load_errors() {
+    let mut planets = PlanetCache::default();
+    let meta = FractoryMeta::new_xyyy(&mut planets);
+    let text = meta.to_ron();
+    fn kind(text: &str, planets: &PlanetCache) -> io::ErrorKind {
+        FractoryMeta::from_ron(text, planets).unwrap_err().kind()
+    }
+    assert_eq!(
+        kind(&text, &PlanetCache::default()),
+        io::ErrorKind::NotFound
+    );
+    assert_eq!(kind("(planet: 3)", &planets), io::ErrorKind::InvalidData);
+    let broken = text.replacen("(\"U", "(\"X", 1);
+    assert_eq!(kind(&broken, &planets), io::ErrorKind::InvalidData);
+}
+
+/// square fractals only ever get reflected, and tick with their own behaviors
+#[test]
+fn test_square() {
+    use crate::sim::logic::{
+        orientation::Orient,
+        path::{SquareOffset, SquarePos},
+    };
+    use glam::IVec2;
+
+    let square = |x, y| SquarePos {
+        depth: 1,
+        pos: IVec2 { x, y },
+    };
+    let one = Tile::ONE;
+    let two = Tile {
+        id: 2,
+        orient: Orient::RtK,
+    };
+    let (mut fractal, orients) =
+        Fractal::<SquarePos>::from_leaves(&[Quad([one; 4]), Quad([one, one, two, one])]).unwrap();
+    assert_eq!(orients, [Orient::Iso, Orient::RtK]);
+
+    fractal.set(SquarePos::UNIT, Tile::SPACE);
+    for transform in [Transform::KU, Transform::FU] {
+        for pos in [square(0, 0), square(1, 0), square(0, 1), square(1, 1)] {
+            fractal.set(pos, two + transform);
+            assert_eq!(fractal.get(pos), two + transform);
+            let root = fractal.get(SquarePos::UNIT);
+            assert!(SquarePos::TRANSFORMS.contains(&root.orient.transform()));
+            fractal.set(pos, Tile::SPACE);
+        }
+    }
+
+    // moves right, which a reflected tile sees as moving down
+    let behavior = [TargetedAction::new(
+        SquareOffset::ZERO,
+        TileAction::Move(
+            SquareOffset {
+                depth: 0,
+                offset: IVec2::X,
+            },
+            Transform::KU,
+        ),
+    )];
+    let behavior = |id| if id == 2 { &behavior[..] } else { &[] };
+
+    let mut fractory = Fractory::new(fractal);
+    fractory.fractal.set(square(0, 0), two);
+    fractory.activate(square(0, 0));
+    let report = fractory.tick_with(behavior, CollisionPolicy::Cancel);
+    assert_eq!(report.moves.len(), 1);
+    assert_eq!(fractory.fractal.get(square(0, 0)), Tile::SPACE);
+    assert_eq!(fractory.fractal.get(square(1, 0)), two);
+
+    fractory.fractal.set(square(1, 0), Tile::SPACE);
+    fractory.fractal.set(square(0, 0), two + Transform::FU);
+    fractory.activate(square(0, 0));
+    fractory.tick_with(behavior, CollisionPolicy::Cancel);
+    assert_eq!(fractory.fractal.get(square(1, 0)), Tile::SPACE);
+    assert_eq!(fractory.fractal.get(square(0, 1)), two + Transform::FU);
+}
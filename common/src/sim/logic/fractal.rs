@@ -2,11 +2,15 @@
 mod tests;
 
 use super::{
-    orientation::{Symmetries, Transform},
-    path::TilePos,
+    orientation::{Orient, Symmetries, Transform},
+    path::{Position, TilePos},
     tile::{Quad, SubTile, Tile},
 };
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    marker::PhantomData,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TileFill {
@@ -61,8 +65,11 @@ pub struct SlotInfo {
 
 /// a quadtree specialized to not have root nodes,
 /// instead relying on reference cycles to create a fractal
+///
+/// `P` decides how tiles are split up and located, such as triangles ([`TilePos`]).
+/// the hash-consing doesn't care about the shape of a tile.
 #[derive(Debug, Default)]
-pub struct Fractal {
+pub struct Fractal<P = TilePos> {
     /// the root node of the fractal; the biggest piece
     pub root: Tile,
 
@@ -73,9 +80,11 @@ pub struct Fractal {
     /// a mapping from quadtile to tile
     /// the opposite of library
     pub recognizer: HashMap<Quad<Tile>, Tile>,
+
+    topology: PhantomData<P>,
 }
 
-impl Fractal {
+impl<P: Position> Fractal<P> {
     // TODO: make Fragment data structure, then implement this function
     // pub fn load_base(root: Tile, nodes: impl IntoIterator<Item = Fragment>) -> Self {
     //     todo!("get fragment data such as symmetries, composition, and behaviors")
@@ -89,18 +98,26 @@ impl Fractal {
         out
     }
 
-    /// creates a fractal with some leaf tiles.
-    /// fails if any of the quads aren't upright or aren't completely filled.
-    pub fn new(leaf_quads: &[Quad<Tile>]) -> Result<Self, ()> {
+    /// creates a fractal with some leaf tiles, where the nth quad becomes fragment n + 1.
+    /// quads that aren't upright are stored in their upright form.
+    /// fails if any of the quads aren't completely filled or are duplicates.
+    pub fn new(leaf_quads: &[Quad<Tile>]) -> Result<Self, FractalError> {
+        Self::from_leaves(leaf_quads).map(|(fractal, _)| fractal)
+    }
+
+    /// like `new`, but also returns how each quad was oriented compared to its upright form,
+    /// which is how tiles of that fragment have to be oriented to look like the quad.
+    pub fn from_leaves(leaf_quads: &[Quad<Tile>]) -> Result<(Self, Vec<Orient>), FractalError> {
         let mut out = Self::new_space();
 
-        for quad in leaf_quads.iter().copied() {
-            out.register_leaf(quad)?;
-        }
+        let orients = leaf_quads
+            .iter()
+            .map(|&quad| out.register_leaf(quad))
+            .collect::<Result<Vec<_>, _>>()?;
 
         out.validate()?;
 
-        Ok(out)
+        Ok((out, orients))
     }
 
     /// TODO: FOR TESTING PURPOSES
@@ -108,31 +125,24 @@ impl Fractal {
         Self::new(&[Quad::ONE]).unwrap()
     }
 
-    /// TODO: FOR TESTING PURPOSES
-    pub fn new_xyyy() -> Self {
-        // TODO: take fragments as argument
-        Self::new(&[
-            Quad::X,
-            Quad::Y,
-            Quad::Z,
-            Quad::W,
-            Quad::ROTOR,
-            Quad::GROWER,
-            Quad::SUCKER,
-            Quad::WIRE,
-        ])
-        .unwrap()
-    }
-
-    fn validate(&self) -> Result<(), ()> {
-        if self.library.get(0).ok_or(())?.fill != TileFill::Empty {
-            return Err(());
-        }
-        for info in &self.library[1..] {
+    fn validate(&self) -> Result<(), FractalError> {
+        debug_assert_eq!(self.library[0].fill, TileFill::Empty);
+        for (fragment, info) in self.library.iter().enumerate().skip(1) {
+            if let Some(child) = info
+                .quad
+                .0
+                .iter()
+                .find(|child| child.id >= self.library.len())
+            {
+                return Err(FractalError::UnknownFragment {
+                    fragment,
+                    id: child.id,
+                });
+            }
             let subtile_fills = info.quad.map(|child| self.library[child.id].fill);
             let fill = TileFill::infer(subtile_fills);
             if !fill.is_full() {
-                return Err(());
+                return Err(FractalError::NotFilled { fragment });
             }
         }
 
@@ -143,8 +153,13 @@ impl Fractal {
         self.library[tile_id]
     }
 
-    pub fn get(&self, path: TilePos) -> Tile {
-        let mut tile = self.root;
+    pub fn get(&self, path: P) -> Tile {
+        self.get_in(self.root, path)
+    }
+
+    /// like `get`, but looks inside of any tree in this fractal, such as an older root.
+    pub fn get_in(&self, root: Tile, path: P) -> Tile {
+        let mut tile = root;
         for subtile in path {
             let mut quad = self.library[tile.id].quad;
             quad += tile.orient.transform();
@@ -153,7 +168,7 @@ impl Fractal {
         tile
     }
 
-    pub fn set(&mut self, path: TilePos, tile: Tile) -> Tile {
+    pub fn set(&mut self, path: P, tile: Tile) -> Tile {
         // expand each child in the path
         let mut cur_tile = self.root;
         let expansions = path
@@ -182,22 +197,175 @@ impl Fractal {
         cur_tile
     }
 
+    /// iterates over every non-empty leaf, along with its position and fill.
+    pub fn leaves(&self) -> Tiles<'_, P> {
+        self.iter_region(P::UNIT)
+    }
+
+    /// iterates over every non-empty leaf under `pos`, including `pos` itself.
+    pub fn iter_region(&self, pos: P) -> Tiles<'_, P> {
+        Tiles {
+            fractal: self,
+            stack: vec![(pos, self.get(pos))],
+            depth: None,
+        }
+    }
+
+    /// iterates over every non-empty tile exactly `depth` layers down,
+    /// whether or not it's inside of a leaf.
+    pub fn tiles_at_depth(&self, depth: u8) -> Tiles<'_, P> {
+        Tiles {
+            fractal: self,
+            stack: vec![(P::UNIT, self.root)],
+            depth: Some(depth),
+        }
+    }
+
+    /// finds how much of `root` each leaf fragment covers,
+    /// where a leaf `d` layers down covers `4^-d` of it.
+    ///
+    /// every distinct tile is only counted once,
+    /// so this stays fast no matter how deep the tree goes.
+    pub fn census(&self, root: Tile) -> BTreeMap<usize, f64> {
+        let mut memo = HashMap::<usize, BTreeMap<usize, f64>>::new();
+        let mut stack = vec![root.id];
+        while let Some(&id) = stack.last() {
+            if memo.contains_key(&id) {
+                stack.pop();
+                continue;
+            }
+            let SlotInfo { quad, fill, .. } = self.library[id];
+            let census = match fill {
+                TileFill::Empty => BTreeMap::new(),
+                TileFill::Full { is_leaf: true } => BTreeMap::from([(id, 1.0)]),
+                _ => {
+                    // count the children first
+                    let missing = quad.0.map(|child| child.id);
+                    let missing = missing.iter().filter(|child| !memo.contains_key(child));
+                    let len = stack.len();
+                    stack.extend(missing);
+                    if stack.len() > len {
+                        continue;
+                    }
+
+                    let mut census = BTreeMap::new();
+                    for child in quad {
+                        for (&leaf, &area) in &memo[&child.id] {
+                            *census.entry(leaf).or_insert(0.0) += area / 4.0;
+                        }
+                    }
+                    census
+                }
+            };
+            memo.insert(id, census);
+            stack.pop();
+        }
+        memo.remove(&root.id).unwrap_or_default()
+    }
+
+    /// finds every position whose tile matches `pattern` transformed by one of `transforms`,
+    /// along with the first transform that matched.
+    ///
+    /// searches down to leaves, but not inside of them.
+    /// empty regions count as a single space tile,
+    /// so they're only reported when `pattern` itself matches space.
+    pub fn find(&self, pattern: &Pattern, transforms: &[Transform]) -> Vec<(P, Transform)> {
+        let matches_space = transforms
+            .iter()
+            .any(|&transform| self.matches(Tile::SPACE, pattern, transform));
+        let mut out = vec![];
+        let mut stack = vec![(P::UNIT, self.root)];
+        while let Some((pos, tile)) = stack.pop() {
+            let SlotInfo { mut quad, fill, .. } = self.library[tile.id];
+            if fill == TileFill::Empty && !matches_space {
+                continue;
+            }
+            if let Some(&transform) = transforms
+                .iter()
+                .find(|&&transform| self.matches(tile, pattern, transform))
+            {
+                out.push((pos, transform));
+            }
+            if fill.is_leaf() {
+                continue;
+            }
+            quad += tile.orient.transform();
+            for subtile in SubTile::QUAD.0.into_iter().rev() {
+                let mut child_pos = pos;
+                child_pos.push_back(subtile);
+                stack.push((child_pos, quad[subtile]));
+            }
+        }
+        out
+    }
+
+    /// finds the smallest regions where 2 trees in this fractal differ.
+    ///
+    /// identical subtrees are skipped without looking inside of them,
+    /// and leaves that differ are reported as a whole.
+    pub fn diff(&self, root_a: Tile, root_b: Tile) -> Vec<P> {
+        let mut out = vec![];
+        let mut stack = vec![(P::UNIT, root_a, root_b)];
+        while let Some((pos, a, b)) = stack.pop() {
+            if a == b {
+                continue;
+            }
+            let (a_info, b_info) = (self.library[a.id], self.library[b.id]);
+            if a_info.fill.is_full() && a_info.fill.is_leaf()
+                || b_info.fill.is_full() && b_info.fill.is_leaf()
+            {
+                out.push(pos);
+                continue;
+            }
+
+            let (mut a_quad, mut b_quad) = (a_info.quad, b_info.quad);
+            a_quad += a.orient.transform();
+            b_quad += b.orient.transform();
+            for subtile in SubTile::QUAD.0.into_iter().rev() {
+                let mut child_pos = pos;
+                child_pos.push_back(subtile);
+                stack.push((child_pos, a_quad[subtile], b_quad[subtile]));
+            }
+        }
+        out
+    }
+
+    /// checks if `tile` looks like `pattern` after transforming the pattern.
+    fn matches(&self, tile: Tile, pattern: &Pattern, transform: Transform) -> bool {
+        match pattern {
+            Pattern::Any => true,
+            // thanks to hash-consing, equal subtrees always have equal tiles
+            &Pattern::Tile(expected) => tile == expected + transform,
+            Pattern::Quad(patterns) => {
+                let mut quad = self.library[tile.id].quad;
+                quad += tile.orient.transform();
+                SubTile::QUAD.0.into_iter().all(|subtile| {
+                    self.matches(quad[subtile + transform], &patterns[subtile], transform)
+                })
+            }
+        }
+    }
+
     /// finds (or registers) a quadtile, and returns the Tile { id, orientation }
-    fn register(&mut self, quad: Quad<Tile>) -> Tile {
+    pub(crate) fn register(&mut self, quad: Quad<Tile>) -> Tile {
         self.recognizer
             .get(&quad)
             .copied()
             .unwrap_or_else(|| self.register_new(quad))
     }
 
-    /// registers a new leaf quadtile into the library.
-    /// returns Err if the tile isn't upright.
-    fn register_leaf(&mut self, mut quad: Quad<Tile>) -> Result<(), ()> {
-        let orient = quad.reorient();
-        if !orient.is_upright() {
-            return Err(());
-        }
+    /// registers a new leaf quadtile into the library, in its upright form.
+    /// returns the orientation of the quad that was passed in,
+    /// or Err if the same quad was already registered.
+    fn register_leaf(&mut self, mut quad: Quad<Tile>) -> Result<Orient, FractalError> {
         let id = self.library.len();
+        let orient = quad.reorient_within(P::TRANSFORMS);
+        if let Some(original) = self.recognizer.get(&quad) {
+            return Err(FractalError::Duplicate {
+                fragment: id,
+                original: original.id,
+            });
+        }
 
         self.library.push(SlotInfo {
             quad,
@@ -205,13 +373,19 @@ impl Fractal {
             symmetries: orient.symmetries(),
         });
 
-        self.cache(quad, Tile { id, orient });
-        Ok(())
+        self.cache(
+            quad,
+            Tile {
+                id,
+                orient: orient.upright(),
+            },
+        );
+        Ok(orient)
     }
 
     /// registers a new non-leaf quadtile into the library.
     fn register_new(&mut self, mut quad: Quad<Tile>) -> Tile {
-        let orient = quad.reorient();
+        let orient = quad.reorient_within(P::TRANSFORMS);
         let id = self.library.len();
 
         let sub_info = quad.map(|child| self.library[child.id].fill);
@@ -236,8 +410,18 @@ impl Fractal {
 
     // TODO: 6 hash inserts per new tile is probably expensive for the common case.
     fn cache(&mut self, mut quad: Quad<Tile>, mut tile: Tile) {
-        for _reflection in 0..2 {
-            for _rotation in 0..3 {
+        let rotations = if P::TRANSFORMS.contains(&Transform::KR) {
+            3
+        } else {
+            1
+        };
+        let reflections = if P::TRANSFORMS.contains(&Transform::FU) {
+            2
+        } else {
+            1
+        };
+        for _reflection in 0..reflections {
+            for _rotation in 0..rotations {
                 let result = self.recognizer.insert(quad, tile);
                 assert!(
                     result.is_none(),
@@ -259,6 +443,106 @@ impl Fractal {
     }
 }
 
+/// why a fractal couldn't be built from its leaves.
+///
+/// fragments are referred to by id.
+/// [`FragmentRegistry::build_fractal`](super::fragment::FragmentRegistry::build_fractal)
+/// refers to them by name instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalError {
+    /// the fragment has empty or partially filled parts
+    NotFilled { fragment: usize },
+    /// the fragment is made of the same tiles as another fragment, possibly in another orientation
+    Duplicate { fragment: usize, original: usize },
+    /// the fragment is made of a fragment that doesn't exist
+    UnknownFragment { fragment: usize, id: usize },
+}
+
+impl Display for FractalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::NotFilled { fragment } => {
+                write!(f, "fragment #{fragment} is not completely filled")
+            }
+            Self::Duplicate { fragment, original } => {
+                write!(
+                    f,
+                    "fragment #{fragment} has the same composition as #{original}"
+                )
+            }
+            Self::UnknownFragment { fragment, id } => {
+                write!(
+                    f,
+                    "fragment #{fragment} is made of #{id}, which doesn't exist"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for FractalError {}
+
+/// a piece of a fractal to search for with [`Fractal::find`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// matches any tile
+    Any,
+    /// matches exactly this tile, and everything inside of it
+    Tile(Tile),
+    /// matches a tile whose children match each of these
+    Quad(Box<Quad<Pattern>>),
+}
+
+impl From<Tile> for Pattern {
+    fn from(tile: Tile) -> Self {
+        Self::Tile(tile)
+    }
+}
+
+impl From<Quad<Pattern>> for Pattern {
+    fn from(quad: Quad<Pattern>) -> Self {
+        Self::Quad(Box::new(quad))
+    }
+}
+
+/// an iterator over the tiles in a fractal, outermost first.
+///
+/// each tile is oriented the same way [`Fractal::get`] would return it.
+pub struct Tiles<'a, P = TilePos> {
+    fractal: &'a Fractal<P>,
+    stack: Vec<(P, Tile)>,
+    /// the depth to stop at, or `None` to stop at leaves
+    depth: Option<u8>,
+}
+
+impl<P: Position> Iterator for Tiles<'_, P> {
+    type Item = (P, Tile, TileFill);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((pos, tile)) = self.stack.pop() {
+            let SlotInfo { mut quad, fill, .. } = self.fractal.library[tile.id];
+            if fill == TileFill::Empty {
+                continue;
+            }
+            let found = match self.depth {
+                Some(depth) => pos.depth() >= depth as usize,
+                None => fill.is_leaf(),
+            };
+            if found {
+                return Some((pos, tile, fill));
+            }
+
+            quad += tile.orient.transform();
+            for subtile in SubTile::QUAD.0.into_iter().rev() {
+                let mut child_pos = pos;
+                child_pos.push_back(subtile);
+                self.stack.push((child_pos, quad[subtile]));
+            }
+        }
+        None
+    }
+}
+
 pub use only_for_reference::Fractal as BoringFractal;
 mod only_for_reference {
     use indexmap::IndexSet;
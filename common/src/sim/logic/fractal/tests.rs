@@ -1,6 +1,351 @@
 use super::*;
+use crate::sim::logic::{
+    factory::Fractory,
+    fragment::{Appearance, Fragment, FragmentRegistry, RegistryError},
+    planet::Planet,
+};
 
-// #[test]
+#[test]
 fn test_register() {
-    // you know the current algorithm works
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let composition = |name| fragments.by_name(name).unwrap().composition;
+
+    let mut fractal = Fractal::<TilePos>::new_space();
+    for name in ["X", "Y"] {
+        assert_eq!(fractal.register_leaf(composition(name)), Ok(Orient::Iso));
+    }
+
+    // leaves that aren't upright are stored upright
+    let flip_flop = composition("Flip-Flop");
+    let mut rotated = flip_flop;
+    rotated += Transform::KR;
+    let orient = fractal.register_leaf(rotated).unwrap();
+    assert!(!orient.is_upright());
+    assert_eq!(fractal.library[3].quad, flip_flop);
+    let mut expanded = fractal.library[3].quad;
+    expanded += orient.transform();
+    assert_eq!(expanded, rotated);
+    assert_eq!(
+        fractal.get(TilePos::UNIT),
+        Tile::SPACE,
+        "registering leaves shouldn't touch the root"
+    );
+
+    // so are duplicates in any orientation
+    let mut duplicate = flip_flop;
+    duplicate += Transform::FL;
+    assert_eq!(
+        fractal.register_leaf(duplicate),
+        Err(FractalError::Duplicate {
+            fragment: 4,
+            original: 3
+        })
+    );
+}
+
+#[test]
+fn test_new_errors() {
+    let one = Tile::ONE;
+    let result = Fractal::<TilePos>::new(&[Quad::ONE, Quad([one, Tile::SPACE, one, one])]);
+    assert_eq!(result.unwrap_err(), FractalError::NotFilled { fragment: 2 });
+
+    let missing = Tile { id: 5, ..one };
+    let result = Fractal::<TilePos>::new(&[Quad([one, one, one, missing])]);
+    assert_eq!(
+        result.unwrap_err(),
+        FractalError::UnknownFragment { fragment: 1, id: 5 }
+    );
+
+    let mut fragments = FragmentRegistry::new();
+    for name in ["A", "B"] {
+        fragments
+            .register(Fragment {
+                name: name.into(),
+                desc: String::new(),
+                composition: Quad([Tile { id: 1, ..one }; 4]),
+                behavior: vec![],
+                appearance: Appearance::default(),
+            })
+            .unwrap();
+    }
+    // the registry knows the fragments by name
+    let error = fragments.build_fractal().unwrap_err();
+    assert_eq!(
+        error,
+        RegistryError::Duplicate {
+            fragment: "B".into(),
+            original: "A".into()
+        }
+    );
+    assert_eq!(error.to_string(), r#""B" has the same composition as "A""#);
+}
+
+#[test]
+fn test_from_leaves() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let mut leaves = fragments
+        .iter()
+        .skip(1)
+        .map(|(_, fragment)| fragment.composition)
+        .collect::<Vec<_>>();
+    let flip_flop = fragments.id("Flip-Flop").unwrap();
+    leaves[flip_flop - 1] += Transform::KR;
+
+    let (fractal, orients) = Fractal::<TilePos>::from_leaves(&leaves).unwrap();
+    assert_eq!(orients.len(), leaves.len());
+    // tiles oriented as returned look like the quads that were passed in
+    for (id, (&orient, &quad)) in (1..).zip(orients.iter().zip(&leaves)) {
+        let tile = Tile { id, orient };
+        let children =
+            SubTile::QUAD.map(|subtile| fractal.get_in(tile, TilePos::from_inward_path([subtile])));
+        assert_eq!(children, quad, "fragment #{id}");
+    }
+    assert_eq!(orients[0], Orient::Iso);
+    assert_ne!(
+        orients[flip_flop - 1],
+        fragments.tile("Flip-Flop").unwrap().orient
+    );
+}
+
+/// the same as `iter_region`, but with the obvious recursive implementation
+fn leaves_under(fractal: &Fractal, pos: TilePos, out: &mut Vec<(TilePos, Tile, TileFill)>) {
+    let tile = fractal.get(pos);
+    let fill = fractal.get_info(tile.id).fill;
+    if fill == TileFill::Empty {
+        return;
+    }
+    if fill.is_leaf() {
+        out.push((pos, tile, fill));
+        return;
+    }
+    for subtile in SubTile::QUAD.0 {
+        let mut child = pos;
+        child.push_back(subtile);
+        leaves_under(fractal, child, out);
+    }
+}
+
+#[test]
+fn test_leaves() {
+    let planet = Planet::new_xyyy();
+    let fractory = Fractory::new_xyyy(planet.fragments());
+    let fractal = &fractory.fractal;
+
+    let mut expected = vec![];
+    leaves_under(fractal, TilePos::UNIT, &mut expected);
+    assert!(!expected.is_empty());
+    assert_eq!(fractal.leaves().collect::<Vec<_>>(), expected);
+
+    let region = TilePos::from_inward_path([SubTile::U, SubTile::C]);
+    let mut expected = vec![];
+    leaves_under(fractal, region, &mut expected);
+    assert_eq!(fractal.iter_region(region).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_tiles_at_depth() {
+    let planet = Planet::new_xyyy();
+    let fractory = Fractory::new_xyyy(planet.fragments());
+    let fractal = &fractory.fractal;
+
+    for depth in 0..6 {
+        // every tile at this depth, including the ones inside of leaves
+        let mut positions = vec![TilePos::UNIT];
+        for _ in 0..depth {
+            positions = positions
+                .into_iter()
+                .flat_map(|pos| {
+                    SubTile::QUAD.0.map(|subtile| {
+                        let mut child = pos;
+                        child.push_back(subtile);
+                        child
+                    })
+                })
+                .collect();
+        }
+        let expected = positions
+            .into_iter()
+            .map(|pos| (pos, fractal.get(pos)))
+            .map(|(pos, tile)| (pos, tile, fractal.get_info(tile.id).fill))
+            .filter(|(_, _, fill)| *fill != TileFill::Empty)
+            .collect::<Vec<_>>();
+        assert_eq!(fractal.tiles_at_depth(depth).collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn test_census() {
+    let planet = Planet::new_xyyy();
+    let fractory = Fractory::new_xyyy(planet.fragments());
+    let fractal = &fractory.fractal;
+
+    let mut expected = BTreeMap::<usize, f64>::new();
+    for (pos, tile, _) in fractal.leaves() {
+        *expected.entry(tile.id).or_insert(0.0) += 0.25_f64.powi(pos.depth() as i32);
+    }
+    assert_eq!(fractal.census(fractal.root), expected);
+    assert_eq!(fractal.census(Tile::SPACE), BTreeMap::new());
+}
+
+#[test]
+fn test_census_deep() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let (x, y) = (fragments.tile("X").unwrap(), fragments.tile("Y").unwrap());
+    let mut fractal = fragments.build_fractal().unwrap();
+    let path = |subtile| TilePos::from_inward_path([subtile]);
+
+    // each layer is made of 2 copies of the previous layer, an X and a Y,
+    // so the tree ends up with 2^1000 leaves
+    let mut tile = x;
+    let mut x_area = 1.0;
+    for _ in 0..1000 {
+        fractal.root = Tile::SPACE;
+        fractal.set(path(SubTile::C), tile);
+        fractal.set(path(SubTile::U), tile);
+        fractal.set(path(SubTile::R), x);
+        fractal.set(path(SubTile::L), y);
+        tile = fractal.root;
+        x_area = (2.0 * x_area + 1.0) / 4.0;
+    }
+
+    let census = fractal.census(tile);
+    assert!((census[&x.id] - x_area).abs() < 1e-12);
+    assert!((census[&y.id] - (1.0 - x_area)).abs() < 1e-12);
+}
+
+#[test]
+fn test_find() {
+    use SubTile::*;
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let tile = |name| fragments.tile(name).unwrap();
+    let mut fractal = fragments.build_fractal().unwrap();
+    let at = |subtiles: &[SubTile]| TilePos::from_inward_path(subtiles.iter().copied());
+
+    // a small machine, placed 3 times in different orientations
+    let machine = [tile("X"), tile("Flip-Flop"), tile("Spinner"), tile("Y")];
+    let places = [
+        (at(&[C]), Transform::KU),
+        (at(&[U, L]), Transform::KR),
+        (at(&[R, R, C]), Transform::FL),
+    ];
+    for (pos, transform) in places {
+        for (subtile, child) in SubTile::QUAD.0.into_iter().zip(machine) {
+            let mut child_pos = pos;
+            child_pos.push_back(subtile + transform);
+            fractal.set(child_pos, child + transform);
+        }
+    }
+    let upright = fractal.get(at(&[C]));
+
+    let found = fractal.find(&upright.into(), &Transform::TRANSFORMS);
+    assert_eq!(found, places);
+    let found = fractal.find(&upright.into(), &[Transform::KU]);
+    assert_eq!(found, places[..1]);
+
+    // wildcards can match more than the original machine
+    let pattern = Pattern::from(Quad([
+        Pattern::Any,
+        tile("Flip-Flop").into(),
+        tile("Spinner").into(),
+        Pattern::Any,
+    ]));
+    let found = fractal.find(&pattern, &Transform::TRANSFORMS);
+    assert_eq!(found, places);
+    fractal.set(at(&[U, L, C]), tile("Wire"));
+    let found = fractal.find(&pattern, &Transform::TRANSFORMS);
+    assert_eq!(found, places);
+    fractal.set(at(&[U, L, L]), Tile::SPACE);
+    let found = fractal.find(&pattern, &Transform::TRANSFORMS);
+    assert_eq!(found, [places[0], places[2]]);
+}
+
+#[test]
+fn test_find_space() {
+    use SubTile::*;
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let x = fragments.tile("X").unwrap();
+    let mut fractal = fragments.build_fractal().unwrap();
+    let at = |subtiles: &[SubTile]| TilePos::from_inward_path(subtiles.iter().copied());
+    fractal.root = Tile::SPACE;
+    fractal.set(at(&[C]), x);
+    fractal.set(at(&[U, R]), x);
+
+    // empty regions are found as a whole, without looking inside of them
+    let found = fractal.find(&Tile::SPACE.into(), &[Transform::KU]);
+    let empty = [at(&[U, C]), at(&[U, U]), at(&[U, L]), at(&[R]), at(&[L])];
+    assert_eq!(found, empty.map(|pos| (pos, Transform::KU)));
+
+    // space can be asked for inside of a pattern too
+    let pattern = Pattern::from(Quad([
+        Pattern::Any,
+        Pattern::Any,
+        Tile::SPACE.into(),
+        Tile::SPACE.into(),
+    ]));
+    let found = fractal.find(&pattern, &[Transform::KU]);
+    assert_eq!(found[0], (at(&[]), Transform::KU));
+    assert!(found[1..].iter().all(|(pos, _)| empty.contains(pos)));
+
+    // patterns that can't match space never report empty regions
+    let pattern = Pattern::from(Quad([x.into(), Pattern::Any, Pattern::Any, Pattern::Any]));
+    let found = fractal.find(&pattern, &[Transform::KU]);
+    assert_eq!(found[0], (at(&[]), Transform::KU));
+    assert!(found.iter().all(|(pos, _)| !empty.contains(pos)));
+}
+
+#[test]
+fn test_diff() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use SubTile::*;
+
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let fractal = &mut fractory.fractal;
+    let at = |subtiles: &[SubTile]| TilePos::from_inward_path(subtiles.iter().copied());
+    let before = fractal.root;
+    assert_eq!(fractal.diff(before, before), []);
+
+    // a leaf that gets edited is reported as a whole
+    let leaf = fractal.leaves().next().unwrap().0;
+    let mut inside = leaf;
+    inside.push_back(U);
+    fractal.set(inside, Tile::SPACE);
+    fractal.set(at(&[L, L]), fragments.tile("Y").unwrap());
+    assert_eq!(fractal.diff(before, fractal.root), [leaf, at(&[L, L])]);
+
+    // copying every difference over turns one tree into the other
+    let mut rng = StdRng::seed_from_u64(0xD1FF);
+    for _ in 0..64 {
+        fractal.root = before;
+        for _ in 0..rng.gen_range(1..8) {
+            let pos = TilePos::from_inward_path(
+                (0..rng.gen_range(0..6)).map(|_| SubTile::QUAD.0[rng.gen_range(0..4)]),
+            );
+            let id = rng.gen_range(0..fragments.len());
+            let transform = Transform::TRANSFORMS[rng.gen_range(0..6)];
+            fractal.set(
+                pos,
+                fragments.tile(fragments.name(id).unwrap()).unwrap() + transform,
+            );
+        }
+        let after = fractal.root;
+        let diff = fractal.diff(before, after);
+        let changes = diff
+            .iter()
+            .map(|&pos| (pos, fractal.get(pos)))
+            .collect::<Vec<_>>();
+
+        fractal.root = before;
+        for (pos, tile) in changes {
+            assert_ne!(fractal.get(pos), tile);
+            fractal.set(pos, tile);
+        }
+        assert_eq!(fractal.root, after);
+    }
 }
@@ -0,0 +1,260 @@
+#[cfg(test)]
+mod tests;
+
+use super::{
+    fractal::{Fractal, FractalError},
+    orientation::{Orient, Symmetries},
+    planet::Behavior,
+    tile::{Quad, Tile},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+/// how a fragment should be shown to the player.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Appearance {
+    /// a single character to represent the fragment in text
+    pub symbol: char,
+    /// the color to draw the fragment with, as rgb
+    #[serde(default = "Appearance::default_color")]
+    pub color: [u8; 3],
+    /// an image to draw the fragment with, tinted by its color.
+    /// relative to the assets folder, such as `sprites/boccs.png`
+    #[serde(default)]
+    pub sprite: Option<String>,
+}
+
+impl Appearance {
+    fn default_color() -> [u8; 3] {
+        [128; 3]
+    }
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            symbol: '?',
+            color: Self::default_color(),
+            sprite: None,
+        }
+    }
+}
+
+/// everything there is to know about a single kind of fragment.
+#[derive(Debug, Clone)]
+pub struct Fragment {
+    pub name: String,
+    pub desc: String,
+    /// the 4 tiles this fragment is made of, as it looks when placed with [`FragmentRegistry::tile`].
+    /// doesn't have to be upright.
+    pub composition: Quad<Tile>,
+    pub behavior: Behavior,
+    pub appearance: Appearance,
+}
+
+/// every fragment in a planet, indexed by id.
+///
+/// id 0 is always empty space.
+#[derive(Debug, Clone)]
+pub struct FragmentRegistry {
+    /// each fragment, along with the orientation its composition is written in
+    fragments: Vec<(Fragment, Orient)>,
+    ids: HashMap<String, usize>,
+}
+
+impl Default for FragmentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragmentRegistry {
+    /// creates a registry containing only empty space.
+    pub fn new() -> Self {
+        let space = Fragment {
+            name: String::new(),
+            desc: "Nothing.".into(),
+            composition: Quad::SPACE,
+            behavior: vec![],
+            appearance: Appearance {
+                symbol: '.',
+                color: [80; 3],
+                sprite: None,
+            },
+        };
+        Self {
+            fragments: vec![(space, Orient::Iso)],
+            ids: HashMap::from([(String::new(), 0)]),
+        }
+    }
+
+    /// adds a new fragment to the registry, and returns its id.
+    /// fails if another fragment already has its name.
+    ///
+    /// fragments may refer to each other's ids in their compositions,
+    /// including ids that have yet to be registered.
+    pub fn register(&mut self, fragment: Fragment) -> Result<usize, RegistryError> {
+        if self.ids.contains_key(&fragment.name) {
+            return Err(RegistryError::NameTaken(fragment.name));
+        }
+        let id = self.fragments.len();
+        let mut quad = fragment.composition;
+        let orient = quad.reorient();
+        self.ids.insert(fragment.name.clone(), id);
+        self.fragments.push((fragment, orient));
+        Ok(id)
+    }
+
+    /// renames a fragment.
+    /// fails if another fragment already has the new name.
+    ///
+    /// panics if the fragment doesn't exist.
+    pub fn rename(&mut self, id: usize, name: String) -> Result<(), RegistryError> {
+        if self.id(&name).is_some_and(|other| other != id) {
+            return Err(RegistryError::NameTaken(name));
+        }
+        let fragment = &mut self.fragments[id].0;
+        self.ids.remove(&fragment.name);
+        self.ids.insert(name.clone(), id);
+        fragment.name = name;
+        Ok(())
+    }
+
+    /// changes what a fragment is made of.
+    /// fails without changing anything if the fragments would no longer build a fractal.
+    ///
+    /// fractals built before the edit won't know about it,
+    /// so they should be rebuilt with [`Fractory::rebuild`](super::factory::Fractory::rebuild).
+    ///
+    /// panics if the fragment doesn't exist.
+    pub fn set_composition(
+        &mut self,
+        id: usize,
+        mut composition: Quad<Tile>,
+    ) -> Result<(), RegistryError> {
+        let old = self.fragments[id].clone();
+        let (fragment, orient) = &mut self.fragments[id];
+        fragment.composition = composition;
+        *orient = composition.reorient();
+        if let Err(err) = self.build_fractal() {
+            self.fragments[id] = old;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// how many fragments there are, including empty space.
+    pub fn len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Fragment> {
+        self.fragments.get(id).map(|(fragment, _)| fragment)
+    }
+
+    /// finds the id of a fragment by name.
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Fragment> {
+        self.id(name).and_then(|id| self.get(id))
+    }
+
+    /// finds a fragment by name, and returns it as a tile oriented like its composition.
+    pub fn tile(&self, name: &str) -> Option<Tile> {
+        let id = self.id(name)?;
+        Some(Tile {
+            id,
+            orient: self.fragments[id].1,
+        })
+    }
+
+    pub fn symmetries(&self, id: usize) -> Option<Symmetries> {
+        self.fragments
+            .get(id)
+            .map(|(_, orient)| orient.symmetries())
+    }
+
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.get(id).map(|fragment| fragment.name.as_str())
+    }
+
+    pub fn behavior(&self, id: usize) -> Option<&Behavior> {
+        self.get(id).map(|fragment| &fragment.behavior)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Fragment)> {
+        self.fragments
+            .iter()
+            .map(|(fragment, _)| fragment)
+            .enumerate()
+    }
+
+    /// builds a fractal whose leaves are the fragments in this registry.
+    /// fails if any of the compositions aren't completely filled or are duplicates.
+    pub fn build_fractal(&self) -> Result<Fractal, RegistryError> {
+        let leaves = self
+            .iter()
+            .skip(1)
+            .map(|(_, fragment)| fragment.composition)
+            .collect::<Vec<_>>();
+        let (fractal, orients) = Fractal::from_leaves(&leaves).map_err(|err| self.named(err))?;
+        // tiles handed out before building have to fit the fractal's leaves
+        debug_assert!(orients
+            .iter()
+            .zip(&self.fragments[1..])
+            .all(|(orient, (_, expected))| orient == expected));
+        Ok(fractal)
+    }
+
+    /// puts names to the fragments in a fractal's error.
+    /// the fractal's leaves have the same ids as the fragments they were built from.
+    fn named(&self, err: FractalError) -> RegistryError {
+        let name = |id| self.name(id).unwrap_or_default().to_owned();
+        match err {
+            FractalError::NotFilled { fragment } => RegistryError::NotFilled(name(fragment)),
+            FractalError::Duplicate { fragment, original } => RegistryError::Duplicate {
+                fragment: name(fragment),
+                original: name(original),
+            },
+            FractalError::UnknownFragment { fragment, id } => RegistryError::UnknownFragment {
+                fragment: name(fragment),
+                id,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// another fragment already goes by this name.
+    NameTaken(String),
+    /// the fragment has empty or partially filled parts
+    NotFilled(String),
+    /// the fragment is made of the same tiles as another fragment, possibly in another orientation
+    Duplicate { fragment: String, original: String },
+    /// the fragment is made of a fragment that doesn't exist
+    UnknownFragment { fragment: String, id: usize },
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NameTaken(name) => write!(f, "there is already a fragment named {name:?}"),
+            Self::NotFilled(name) => write!(f, "{name:?} is not completely filled"),
+            Self::Duplicate { fragment, original } => {
+                write!(f, "{fragment:?} has the same composition as {original:?}")
+            }
+            Self::UnknownFragment { fragment, id } => {
+                write!(f, "{fragment:?} is made of #{id}, which doesn't exist")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
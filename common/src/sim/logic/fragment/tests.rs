@@ -0,0 +1,79 @@
+use super::*;
+use crate::sim::logic::planet::Planet;
+
+fn named(name: &str) -> Fragment {
+    Fragment {
+        name: name.into(),
+        desc: String::new(),
+        composition: Quad::SPACE,
+        behavior: vec![],
+        appearance: Appearance::default(),
+    }
+}
+
+#[test]
+fn test_register_duplicate() {
+    let mut fragments = FragmentRegistry::new();
+    assert_eq!(fragments.register(named("X")), Ok(1));
+    assert_eq!(
+        fragments.register(named("X")),
+        Err(RegistryError::NameTaken("X".into()))
+    );
+    // empty space already goes by the empty name
+    assert_eq!(
+        fragments.register(named("")),
+        Err(RegistryError::NameTaken("".into()))
+    );
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments.id("X"), Some(1));
+}
+
+#[test]
+fn test_rename() {
+    let mut fragments = FragmentRegistry::new();
+    let x = fragments.register(named("X")).unwrap();
+    fragments.register(named("Y")).unwrap();
+    assert_eq!(
+        fragments.rename(x, "Y".into()),
+        Err(RegistryError::NameTaken("Y".into()))
+    );
+    assert_eq!(fragments.rename(x, "X".into()), Ok(()));
+    assert_eq!(fragments.rename(x, "Z".into()), Ok(()));
+    assert_eq!(fragments.id("Z"), Some(x));
+    assert_eq!(fragments.id("X"), None);
+}
+
+#[test]
+fn test_set_composition() {
+    let planet = Planet::new_xyyy();
+    let mut fragments = planet.fragments().clone();
+    let wire = fragments.id("Wire").unwrap();
+    let before = fragments.get(wire).unwrap().composition;
+
+    // the same composition as X
+    let x = fragments.by_name("X").unwrap().composition;
+    assert!(matches!(
+        fragments.set_composition(wire, x),
+        Err(RegistryError::Duplicate { fragment, original }) if fragment == "Wire" && original == "X"
+    ));
+    assert_eq!(fragments.get(wire).unwrap().composition, before);
+
+    let [x, y] = ["X", "Y"].map(|name| fragments.tile(name).unwrap());
+    fragments.set_composition(wire, Quad([y, x, y, y])).unwrap();
+    assert_eq!(fragments.get(wire).unwrap().composition, Quad([y, x, y, y]));
+    assert!(fragments.build_fractal().is_ok());
+}
+
+#[test]
+fn test_appearance_defaults() {
+    use serde::de::value::{Error, MapDeserializer};
+    let saved = MapDeserializer::<_, Error>::new([("symbol", 'X')].into_iter());
+    let appearance = Appearance::deserialize(saved).unwrap();
+    assert_eq!(
+        appearance,
+        Appearance {
+            symbol: 'X',
+            ..Appearance::default()
+        }
+    );
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests;
+
+use super::{
+    factory::{Fractory, TickReport},
+    fractal::Pattern,
+    orientation::Transform,
+    tile::{Quad, Tile},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// something the player has to do to complete a mission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Goal {
+    /// store this many of a fragment, counting from when the mission started
+    Store { fragment: usize, count: usize },
+    /// have a tile somewhere `depth` layers down that's made of exactly these 4 tiles
+    Pattern { depth: u8, quad: Quad<Tile> },
+    /// keep the fractory running stably for this many ticks in a row.
+    ///
+    /// a tick is stable when something is active, no move gets cancelled,
+    /// and exactly the same fragments get stored as the tick before.
+    RunFor { ticks: u64 },
+}
+
+impl Goal {
+    /// how much progress is needed to complete this goal.
+    pub fn target(&self) -> u64 {
+        match *self {
+            Self::Store { count, .. } => count as u64,
+            Self::Pattern { .. } => 1,
+            Self::RunFor { ticks } => ticks,
+        }
+    }
+}
+
+/// a set of goals to complete in a biome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mission {
+    pub name: String,
+    pub desc: String,
+    pub goals: Vec<Goal>,
+}
+
+impl Mission {
+    /// creates progress for this mission, with nothing done yet.
+    pub fn start(&self) -> MissionProgress {
+        MissionProgress {
+            goals: vec![GoalProgress::default(); self.goals.len()],
+            last_stored: None,
+            checked_root: None,
+        }
+    }
+}
+
+/// how far along a single goal is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub progress: u64,
+    /// goals stay done, even if the progress drops afterwards
+    pub done: bool,
+}
+
+/// how far along each goal of a mission is.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MissionProgress {
+    goals: Vec<GoalProgress>,
+    /// what was stored on the last tick, if there was one
+    #[serde(default)]
+    last_stored: Option<BTreeMap<usize, usize>>,
+    /// the root that patterns were last searched for in.
+    /// the fractal is hash-consed, so nothing new can be found until it changes.
+    #[serde(skip)]
+    checked_root: Option<Tile>,
+}
+
+impl MissionProgress {
+    pub fn goals(&self) -> &[GoalProgress] {
+        &self.goals
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.goals.iter().all(|goal| goal.done)
+    }
+
+    /// updates every goal after a tick.
+    pub fn update(&mut self, mission: &Mission, fractory: &Fractory, report: &TickReport) {
+        let stable = !fractory.activated.is_empty()
+            && report.moves.cancelled() == 0
+            && self
+                .last_stored
+                .as_ref()
+                .is_none_or(|stored| *stored == report.stored);
+        for (goal, progress) in mission.goals.iter().zip(&mut self.goals) {
+            if progress.done {
+                continue;
+            }
+            progress.progress = match *goal {
+                Goal::Store { fragment, .. } => {
+                    let stored = report.stored.get(&fragment).copied().unwrap_or(0);
+                    progress.progress + stored as u64
+                }
+                Goal::Pattern { .. } if self.checked_root == Some(fractory.fractal.root) => {
+                    progress.progress
+                }
+                Goal::Pattern { depth, quad } => {
+                    let pattern = Pattern::from(quad.map(Pattern::from));
+                    let found = fractory
+                        .fractal
+                        .find(&pattern, &[Transform::KU])
+                        .iter()
+                        .any(|(pos, _)| pos.depth() == depth as usize);
+                    found as u64
+                }
+                Goal::RunFor { .. } if stable => progress.progress + 1,
+                Goal::RunFor { .. } => 0,
+            };
+            progress.done = progress.progress >= goal.target();
+        }
+        self.last_stored = Some(report.stored.clone());
+        self.checked_root = Some(fractory.fractal.root);
+    }
+}
@@ -0,0 +1,176 @@
+use super::*;
+use crate::sim::logic::orientation::Transform;
+use crate::sim::logic::{
+    command::{Command, CommandHistory},
+    factory::FractoryMeta,
+    path::TilePos,
+    planet::{BiomeId, Planet, PlanetCache},
+    tile::SubTile,
+};
+
+#[test]
+fn test_store() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&BiomeId::from("Landing Zone")).unwrap();
+    let y = fragments.id("Y").unwrap();
+    let mission = Mission {
+        name: "Store".into(),
+        desc: String::new(),
+        goals: vec![Goal::Store {
+            fragment: y,
+            count: 20,
+        }],
+    };
+
+    let mut fractory = Fractory::new_xyyy(fragments);
+    let mut progress = mission.start();
+    assert!(!progress.is_complete());
+    while !progress.is_complete() {
+        let report = fractory.tick(fragments, biome);
+        progress.update(&mission, &fractory, &report);
+        assert_eq!(
+            progress.goals()[0].progress,
+            fractory.inventory.get(&y).copied().unwrap_or(0) as u64
+        );
+    }
+    assert!(fractory.throughput.ticks() < 100);
+}
+
+#[test]
+fn test_pattern() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    let (x, y) = (fragments.tile("X").unwrap(), fragments.tile("Y").unwrap());
+    let mission = Mission {
+        name: "Pattern".into(),
+        desc: String::new(),
+        goals: vec![Goal::Pattern {
+            depth: 2,
+            quad: Quad([y, y, y, x]),
+        }],
+    };
+
+    let mut fractory = Fractory::new_xyyy(fragments);
+    fractory.fractal.set(TilePos::UNIT, Tile::SPACE);
+    let mut progress = mission.start();
+    let report = TickReport::default();
+    let under = |subtile| TilePos::from_inward_path([SubTile::L, SubTile::R, subtile]);
+
+    for subtile in [SubTile::C, SubTile::U, SubTile::R] {
+        fractory.fractal.set(under(subtile), y);
+        progress.update(&mission, &fractory, &report);
+        assert!(!progress.is_complete());
+    }
+    fractory.fractal.set(under(SubTile::L), x);
+    progress.update(&mission, &fractory, &report);
+    assert!(progress.is_complete());
+
+    // rotated patterns don't count
+    fractory.transform_at(
+        TilePos::from_inward_path([SubTile::L, SubTile::R]),
+        Transform::KR,
+    );
+    let mut progress = mission.start();
+    progress.update(&mission, &fractory, &report);
+    assert!(!progress.is_complete());
+
+    // leaves aren't looked inside of, even though X is made of X, Y, Y, Y
+    let mission = Mission {
+        name: "Inside".into(),
+        desc: String::new(),
+        goals: vec![Goal::Pattern {
+            depth: 2,
+            quad: Quad([x, y, y, y]),
+        }],
+    };
+    fractory.fractal.set(TilePos::UNIT, Tile::SPACE);
+    fractory
+        .fractal
+        .set(TilePos::from_inward_path([SubTile::U]), x);
+    let mut progress = mission.start();
+    progress.update(&mission, &fractory, &report);
+    assert!(!progress.is_complete());
+    // searched once per root
+    assert_eq!(progress.checked_root, Some(fractory.fractal.root));
+}
+
+#[test]
+fn test_run_for() {
+    let mission = Mission {
+        name: "Run".into(),
+        desc: String::new(),
+        goals: vec![Goal::RunFor { ticks: 10 }],
+    };
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    meta.biome = BiomeId::from("Bulldozer");
+    let planet = planets.get(&meta.planet).unwrap();
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+
+    let mut progress = mission.start();
+    let mut tick = |meta: &mut FractoryMeta| {
+        let report = meta.tick(planet.fragments(), biome);
+        progress.update(&mission, &meta.fractory, &report);
+        (report, progress.goals()[0].progress)
+    };
+    // the bulldozer settles down after a few ticks
+    let (report, streak) = tick(&mut meta);
+    assert_eq!((report.moves.cancelled(), streak), (0, 1));
+    let (report, streak) = tick(&mut meta);
+    assert_eq!((report.moves.cancelled(), streak), (1, 0));
+    // storing something is a change, and so is stopping
+    let (report, streak) = tick(&mut meta);
+    assert_eq!((report.stored.is_empty(), streak), (false, 0));
+    let (report, streak) = tick(&mut meta);
+    assert_eq!((report.stored.is_empty(), streak), (true, 0));
+    for ticks in 1..5 {
+        assert_eq!(tick(&mut meta).1, ticks);
+    }
+
+    // the streak breaks once nothing is active
+    meta.fractory.activated = Default::default();
+    assert_eq!(tick(&mut meta).1, 0);
+
+    // the biome's own missions get tracked by the meta
+    assert_eq!(
+        meta.missions.keys().collect::<Vec<_>>(),
+        biome.missions().iter().map(|m| &m.name).collect::<Vec<_>>()
+    );
+
+    // the landing zone's grow farm never stops bumping into itself
+    let mut meta = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+    let mut progress = mission.start();
+    for _ in 0..10 {
+        let report = meta.tick(planet.fragments(), biome);
+        progress.update(&mission, &meta.fractory, &report);
+        assert_eq!(progress.goals()[0].progress, 0);
+    }
+}
+
+#[test]
+fn test_undo_tick() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    meta.biome = BiomeId::from("Bulldozer");
+    let planet = planets.get(&meta.planet).unwrap();
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+    let mut history = CommandHistory::default();
+
+    let mut run = |meta: &mut FractoryMeta, command| {
+        meta.execute(command, planet.fragments(), biome, &mut history)
+            .unwrap();
+    };
+    run(&mut meta, Command::Tick);
+    let once = meta.missions.clone();
+    assert_eq!(once["Perpetual Motion"].goals()[0].progress, 1);
+
+    // undoing a tick undoes its progress too, so ticking again doesn't count twice
+    run(&mut meta, Command::Tick);
+    run(&mut meta, Command::Undo);
+    assert_eq!(meta.missions, once);
+    run(&mut meta, Command::Undo);
+    run(&mut meta, Command::Tick);
+    assert_eq!(meta.missions, once);
+}
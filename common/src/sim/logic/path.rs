@@ -8,9 +8,15 @@ use super::{
     orientation::{Rotation, Transform},
     tile::SubTile,
 };
-use std::ops::{Add, AddAssign, Mul};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    ops::{Add, AddAssign, Mul},
+    str::FromStr,
+};
 
 use glam::IVec2;
+use serde::{Deserialize, Serialize};
 
 /// glam doesn't have one 😠
 struct IMat2 {
@@ -151,7 +157,7 @@ template
 /// An offset that can be added to a TilePos, or rotated and reflected.
 ///
 /// Can only move within the same level or deeper, not higher.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct TileOffset {
     pub depth: u8,
     pub offset: IVec2,
@@ -307,6 +313,9 @@ impl TilePos {
         flop: false,
     };
 
+    /// the deepest a position can go before its coordinates overflow
+    pub const MAX_DEPTH: u8 = 30;
+
     pub fn from_inward_path(path_iter: impl IntoIterator<Item = SubTile>) -> Self {
         let mut out = Self::UNIT;
         for subtile in path_iter {
@@ -459,27 +468,397 @@ impl DoubleEndedIterator for TilePos {
     }
 }
 
-// TODO: support other topologies
-// /// an absolute position in a quadtree
-// trait Position: Default + AddAssign<Self::Relative> {
-//     /// an offset, which can be added to get a new position in a quadtree
-//     type Relative;
+/// why a position or offset couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePosError {
+    /// neither a path like `CURL`, nor coordinates like `d3:1,2f`
+    Malformed(String),
+    /// deeper than [`TilePos::MAX_DEPTH`]
+    TooDeep(String),
+    /// the coordinates are outside of the fractal
+    OutOfBounds(TilePos),
+}
+
+impl Display for ParsePosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(text) => write!(f, "{text:?} is not a path or coordinates"),
+            Self::TooDeep(text) => write!(f, "{text:?} is too deep"),
+            Self::OutOfBounds(pos) => write!(f, "{pos:#} is out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for ParsePosError {}
+
+fn write_coords(
+    f: &mut std::fmt::Formatter<'_>,
+    depth: u8,
+    pos: IVec2,
+    flop: bool,
+) -> std::fmt::Result {
+    write!(f, "d{depth}:{},{}", pos.x, pos.y)?;
+    if flop {
+        write!(f, "f")?;
+    }
+    Ok(())
+}
+
+/// reads coordinates written as `d<depth>:<x>,<y>`, with an `f` at the end if flopped.
+fn parse_coords(text: &str) -> Result<(u8, IVec2, bool), ParsePosError> {
+    let malformed = || ParsePosError::Malformed(text.to_owned());
+    let (depth, pos) = text
+        .strip_prefix('d')
+        .and_then(|text| text.split_once(':'))
+        .ok_or_else(malformed)?;
+    let (pos, flop) = match pos.strip_suffix('f') {
+        Some(pos) => (pos, true),
+        None => (pos, false),
+    };
+    let (x, y) = pos.split_once(',').ok_or_else(malformed)?;
+    let depth = depth.parse().map_err(|_| malformed())?;
+    let pos = IVec2 {
+        x: x.parse().map_err(|_| malformed())?,
+        y: y.parse().map_err(|_| malformed())?,
+    };
+    if depth > TilePos::MAX_DEPTH {
+        return Err(ParsePosError::TooDeep(text.to_owned()));
+    }
+    Ok((depth, pos, flop))
+}
+
+/// writes a position as its path inward from the root, such as `CURL`,
+/// or `.` for the root itself.
+///
+/// `{:#}` writes its coordinates instead, such as `d3:1,2f`.
+impl Display for TilePos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return write_coords(f, self.depth, self.pos, self.flop);
+        }
+        if self.depth == 0 {
+            return write!(f, ".");
+        }
+        for subtile in *self {
+            write!(f, "{subtile:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// reads a position written either way [`Display`] writes it.
+impl FromStr for TilePos {
+    type Err = ParsePosError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('d') {
+            let (depth, pos, flop) = parse_coords(s)?;
+            let pos = Self { depth, pos, flop };
+            return if pos.is_valid() {
+                Ok(pos)
+            } else {
+                Err(ParsePosError::OutOfBounds(pos))
+            };
+        }
+        if s == "." {
+            return Ok(Self::UNIT);
+        }
+
+        let path = s.chars().map(|c| match c {
+            'C' => Some(SubTile::C),
+            'U' => Some(SubTile::U),
+            'R' => Some(SubTile::R),
+            'L' => Some(SubTile::L),
+            _ => None,
+        });
+        let path = path
+            .collect::<Option<Vec<_>>>()
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| ParsePosError::Malformed(s.to_owned()))?;
+        if path.len() > Self::MAX_DEPTH as usize {
+            return Err(ParsePosError::TooDeep(s.to_owned()));
+        }
+        Ok(Self::from_inward_path(path))
+    }
+}
+
+/// writes an offset as coordinates, such as `d0:-1,-1f`.
+impl Display for TileOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_coords(f, self.depth, self.offset, self.flop)
+    }
+}
+
+impl FromStr for TileOffset {
+    type Err = ParsePosError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (depth, offset, flop) = parse_coords(s)?;
+        Ok(Self {
+            depth,
+            offset,
+            flop,
+        })
+    }
+}
+
+/// an absolute position in a quadtree.
+///
+/// functions like a VecDeque<SubTile> with its push/pop methods,
+/// where each subtile picks one of the 4 children of a tile.
+pub trait Position:
+    Copy
+    + Eq
+    + Hash
+    + Debug
+    + Default
+    + Add<Self::Offset, Output = Option<Self>>
+    + DoubleEndedIterator<Item = SubTile>
+{
+    /// an offset, which can be added to get a new position in a quadtree
+    type Offset: Copy + Debug + AddAssign<Transform> + 'static;
+
+    /// the position of the whole fractal
+    const UNIT: Self;
+
+    /// the transforms a tile at this kind of position can be oriented by
+    const TRANSFORMS: &'static [Transform];
+
+    /// how many layers down this position is
+    fn depth(self) -> usize;
+
+    /// returns whether the position is valid and within bounds
+    fn is_valid(self) -> bool;
+
+    /// broadens the scope of this position one layer shallower,
+    /// placing the old position inside of `placement`
+    fn push_front(&mut self, placement: SubTile);
+
+    /// narrows the scope of this position one layer deeper
+    fn push_back(&mut self, placement: SubTile);
+
+    fn pop_front(&mut self) -> Option<SubTile>;
+
+    fn pop_back(&mut self) -> Option<SubTile>;
+
+    /// the offset that moves this position onto `other`,
+    /// if both are on the same layer.
+    fn step_to(self, other: Self) -> Option<Self::Offset>;
+}
+
+impl Position for TilePos {
+    type Offset = TileOffset;
+
+    const UNIT: Self = Self::UNIT;
+
+    const TRANSFORMS: &'static [Transform] = &Transform::TRANSFORMS;
+
+    fn depth(self) -> usize {
+        self.depth()
+    }
+
+    fn is_valid(self) -> bool {
+        self.is_valid()
+    }
+
+    fn push_front(&mut self, placement: SubTile) {
+        self.push_front(placement)
+    }
+
+    fn push_back(&mut self, placement: SubTile) {
+        self.push_back(placement)
+    }
+
+    fn pop_front(&mut self) -> Option<SubTile> {
+        self.pop_front()
+    }
+
+    fn pop_back(&mut self) -> Option<SubTile> {
+        self.pop_back()
+    }
+
+    fn step_to(self, other: Self) -> Option<TileOffset> {
+        let sign = if self.flop { -1 } else { 1 };
+        (self.depth == other.depth).then_some(TileOffset {
+            depth: 0,
+            offset: (other.pos - self.pos) * sign,
+            flop: self.flop ^ other.flop,
+        })
+    }
+}
+
+/// An offset that can be added to a SquarePos.
+///
+/// Can only move within the same level or deeper, not higher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SquareOffset {
+    pub depth: u8,
+    pub offset: IVec2,
+}
+
+impl SquareOffset {
+    pub const ZERO: Self = Self {
+        depth: 0,
+        offset: IVec2::ZERO,
+    };
+}
+
+impl AddAssign<Transform> for SquareOffset {
+    /// reflecting a square transposes it, so x and y swap places.
+    /// squares have no rotations, so `rhs` must be `KU` or `FU`.
+    fn add_assign(&mut self, rhs: Transform) {
+        debug_assert_eq!(rhs.rotation(), Rotation::U, "squares can't be rotated");
+        if rhs.reflected() {
+            self.offset = IVec2::new(self.offset.y, self.offset.x);
+        }
+    }
+}
+
+/// Locates a specific square inside of a fractal made of squares.
+///
+/// x goes right and y goes down.
+/// the quadrants are laid out so that reflecting a tile (`FU`) transposes it:
+/// C is the top left, U is the bottom right, R is the top right, and L is the bottom left.
+/// rotations don't correspond to any symmetry of a square,
+/// so square fractals should stick to `KU` and `FU`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SquarePos {
+    pub depth: u8,
+    pub pos: IVec2,
+}
+
+impl SquarePos {
+    pub const UNIT: Self = Self {
+        depth: 0,
+        pos: IVec2::ZERO,
+    };
+
+    pub fn from_inward_path(path_iter: impl IntoIterator<Item = SubTile>) -> Self {
+        let mut out = Self::UNIT;
+        for subtile in path_iter {
+            out.push_back(subtile);
+        }
+        out
+    }
+
+    pub fn depth(self) -> usize {
+        self.depth as usize
+    }
+
+    /// how many squares fit along one side at this depth
+    pub fn width(self) -> i32 {
+        1 << self.depth
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.pos.cmpge(IVec2::ZERO).all() && self.pos.cmplt(IVec2::splat(self.width())).all()
+    }
+
+    fn quadrant(placement: SubTile) -> IVec2 {
+        match placement {
+            SubTile::C => IVec2::new(0, 0),
+            SubTile::U => IVec2::new(1, 1),
+            SubTile::R => IVec2::new(1, 0),
+            SubTile::L => IVec2::new(0, 1),
+        }
+    }
+
+    fn placement(IVec2 { x, y }: IVec2) -> SubTile {
+        match (x, y) {
+            (0, 0) => SubTile::C,
+            (1, 1) => SubTile::U,
+            (1, 0) => SubTile::R,
+            (0, 1) => SubTile::L,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn push_front(&mut self, placement: SubTile) {
+        self.pos += Self::quadrant(placement) * self.width();
+        self.depth += 1;
+    }
+
+    pub fn push_back(&mut self, placement: SubTile) {
+        self.pos = self.pos * 2 + Self::quadrant(placement);
+        self.depth += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<SubTile> {
+        self.depth = self.depth.checked_sub(1)?;
+        let w = self.width();
+        let quadrant = self.pos / w;
+        self.pos -= quadrant * w;
+        Some(Self::placement(quadrant))
+    }
+
+    pub fn pop_back(&mut self) -> Option<SubTile> {
+        self.depth = self.depth.checked_sub(1)?;
+        let quadrant = self.pos % 2;
+        self.pos /= 2;
+        Some(Self::placement(quadrant))
+    }
+}
+
+impl Add<SquareOffset> for SquarePos {
+    type Output = Option<Self>;
+
+    /// returns None if out of bounds.
+    fn add(mut self, rhs: SquareOffset) -> Self::Output {
+        self.depth += rhs.depth;
+        self.pos = self.pos * (1 << rhs.depth) + rhs.offset;
+        self.is_valid().then_some(self)
+    }
+}
+
+impl Iterator for SquarePos {
+    type Item = SubTile;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pop_front()
+    }
+}
+
+impl DoubleEndedIterator for SquarePos {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.pop_back()
+    }
+}
+
+impl Position for SquarePos {
+    type Offset = SquareOffset;
 
-//     /// narrows the scope of this position one layer deeper
-//     fn push(&mut self, placement: SubTile);
+    const UNIT: Self = Self::UNIT;
 
-//     /// broadens the scope of this position one layer shallower
-//     fn pop(&mut self) -> Option<SubTile>;
+    const TRANSFORMS: &'static [Transform] = &[Transform::KU, Transform::FU];
 
-//     /// returns whether the position is valid and within bounds
-//     fn is_valid(&self) -> bool;
-// }
+    fn depth(self) -> usize {
+        self.depth()
+    }
 
-// /// a form of topology
-// trait Topology {
-//     /// an absolute position in a quadtree
-//     type Absolute: Position;
+    fn is_valid(self) -> bool {
+        self.is_valid()
+    }
 
-//     /// how to transform a parent tile into a smaller subtile
-//     fn transform(subtile: SubTile) -> Mat4;
-// }
+    fn push_front(&mut self, placement: SubTile) {
+        self.push_front(placement)
+    }
+
+    fn push_back(&mut self, placement: SubTile) {
+        self.push_back(placement)
+    }
+
+    fn pop_front(&mut self) -> Option<SubTile> {
+        self.pop_front()
+    }
+
+    fn pop_back(&mut self) -> Option<SubTile> {
+        self.pop_back()
+    }
+
+    fn step_to(self, other: Self) -> Option<SquareOffset> {
+        (self.depth == other.depth).then_some(SquareOffset {
+            depth: 0,
+            offset: other.pos - self.pos,
+        })
+    }
+}
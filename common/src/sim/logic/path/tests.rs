@@ -156,3 +156,148 @@ fn test_rotate_identities() {
     let a3 = temp;
     assert_eq!(a3, a);
 }
+
+/// same as test_subtiles, but for squares
+#[test]
+fn test_square_subtiles() {
+    let mut positions = HashSet::new();
+    let mut stack = vec![vec![]];
+    while let Some(path) = stack.pop() {
+        let pos = SquarePos::from_inward_path(path.iter().copied());
+        assert!(pos.is_valid());
+        assert!(positions.insert(pos));
+        assert!(pos.eq(path.iter().copied()));
+        assert!(pos.rev().eq(path.iter().rev().copied()));
+
+        let mut front = SquarePos::UNIT;
+        for &subtile in path.iter().rev() {
+            front.push_front(subtile);
+        }
+        assert_eq!(front, pos);
+
+        if path.len() < 4 {
+            for subtile in SubTile::QUAD {
+                let mut child = path.clone();
+                child.push(subtile);
+                stack.push(child);
+            }
+        }
+    }
+    // 1 + 4 + 16 + 64 + 256
+    assert_eq!(positions.len(), 341);
+}
+
+#[test]
+fn test_square_offset() {
+    let pos = SquarePos {
+        depth: 2,
+        pos: IVec2 { x: 1, y: 2 },
+    };
+    let step = SquareOffset {
+        depth: 1,
+        offset: IVec2 { x: 1, y: -4 },
+    };
+    assert_eq!(
+        pos + step,
+        Some(SquarePos {
+            depth: 3,
+            pos: IVec2 { x: 3, y: 0 },
+        })
+    );
+    assert_eq!(
+        pos + SquareOffset {
+            depth: 0,
+            offset: IVec2::X * 3
+        },
+        None
+    );
+
+    let other = SquarePos {
+        depth: 2,
+        pos: IVec2 { x: 3, y: 0 },
+    };
+    assert_eq!(pos + pos.step_to(other).unwrap(), Some(other));
+    assert_eq!(pos.step_to(SquarePos::UNIT), None);
+}
+
+/// the step from one position to another should always land on the other
+#[test]
+fn test_step_to() {
+    let positions = SubTile::QUAD
+        .into_iter()
+        .flat_map(|a| {
+            SubTile::QUAD
+                .into_iter()
+                .map(move |b| TilePos::from_inward_path([a, b]))
+        })
+        .collect::<Vec<_>>();
+    for &src in &positions {
+        for &dst in &positions {
+            assert_eq!(src + src.step_to(dst).unwrap(), Some(dst));
+        }
+        assert_eq!(src.step_to(TilePos::UNIT), None);
+    }
+}
+
+#[test]
+fn test_pos_text() {
+    use SubTile::*;
+    let pos = TilePos::from_inward_path([C, U, R, L]);
+    assert_eq!(pos.to_string(), "CURL");
+    assert_eq!("CURL".parse(), Ok(pos));
+    assert_eq!(TilePos::UNIT.to_string(), ".");
+    assert_eq!(".".parse(), Ok(TilePos::UNIT));
+    assert_eq!("d0:0,0".parse(), Ok(TilePos::UNIT));
+
+    // every position round trips, either way it's written
+    let mut stack = vec![TilePos::UNIT];
+    while let Some(pos) = stack.pop() {
+        assert_eq!(pos.to_string().parse(), Ok(pos));
+        assert_eq!(format!("{pos:#}").parse(), Ok(pos));
+        if pos.depth < 3 {
+            stack.extend(SubTile::QUAD.0.map(|subtile| {
+                let mut child = pos;
+                child.push_back(subtile);
+                child
+            }));
+        }
+    }
+
+    let pos = TilePos {
+        depth: 3,
+        pos: IVec2 { x: 1, y: 2 },
+        flop: true,
+    };
+    assert_eq!(format!("{pos:#}"), "d3:1,2f");
+    assert_eq!("d3:1,2f".parse(), Ok(pos));
+
+    let malformed = |s: &str| Err(ParsePosError::Malformed(s.into()));
+    for s in ["", "CUX", "curl", "d3", "d3:1", "d3:1,2g", "dx:1,2"] {
+        assert_eq!(s.parse::<TilePos>(), malformed(s));
+    }
+    let out_of_bounds = TilePos {
+        depth: 1,
+        pos: IVec2 { x: 2, y: 0 },
+        flop: false,
+    };
+    assert_eq!(
+        "d1:2,0".parse::<TilePos>(),
+        Err(ParsePosError::OutOfBounds(out_of_bounds))
+    );
+    let deep = "U".repeat(31);
+    assert_eq!(deep.parse::<TilePos>(), Err(ParsePosError::TooDeep(deep)));
+    assert!("d31:0,0".parse::<TilePos>().is_err());
+}
+
+#[test]
+fn test_offset_text() {
+    let offset = TileOffset {
+        depth: 1,
+        offset: IVec2 { x: -1, y: -2 },
+        flop: true,
+    };
+    assert_eq!(offset.to_string(), "d1:-1,-2f");
+    assert_eq!("d1:-1,-2f".parse(), Ok(offset));
+    assert_eq!("d0:0,0".parse(), Ok(TileOffset::ZERO));
+    assert!("CURL".parse::<TileOffset>().is_err());
+}
@@ -1,95 +1,370 @@
-use std::{collections::HashMap, ops::Index, rc::Rc};
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs, io,
+    ops::Index,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use glam::IVec2;
+use serde::{Deserialize, Serialize};
 
 use crate::sim::logic::{
     actions::{TargetedAction, TileAction},
-    orientation::Transform,
+    fragment::{Appearance, Fragment, FragmentRegistry},
+    mission::{Goal, Mission},
+    orientation::{Orient, Transform},
     path::TileOffset,
+    tree::collision::CollisionPolicy,
 };
 
-use super::tile::Tile;
+use super::tile::{Quad, Tile};
 
 pub type Behavior = Vec<TargetedAction<TileOffset>>;
 
-// TODO: bitvec
-#[derive(Debug, Clone)]
-pub struct Filter(Vec<bool>);
+/// a set of fragment ids, such as the fragments allowed in a biome.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(try_from = "RawFilter")]
+pub struct Filter {
+    /// how many fragments this filter knows about
+    len: usize,
+    /// one bit per fragment id, with every bit past `len` cleared
+    words: Vec<u64>,
+}
+
+/// a filter as it was saved, before it's checked.
+#[derive(Deserialize)]
+struct RawFilter {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl TryFrom<RawFilter> for Filter {
+    type Error = FilterError;
+
+    fn try_from(RawFilter { len, words }: RawFilter) -> Result<Self, Self::Error> {
+        if words.len() != len.div_ceil(Self::BITS) {
+            return Err(FilterError::WrongLength {
+                len,
+                words: words.len(),
+            });
+        }
+        let mut out = Self { len, words };
+        out.mask();
+        Ok(out)
+    }
+}
 
 impl Filter {
-    fn all(frag_count: usize) -> Self {
-        Self(vec![true; frag_count])
+    const BITS: usize = u64::BITS as usize;
+
+    /// a filter that allows none of the fragments.
+    pub fn none(frag_count: usize) -> Self {
+        Self {
+            len: frag_count,
+            words: vec![0; frag_count.div_ceil(Self::BITS)],
+        }
+    }
+
+    /// a filter that allows all of the fragments.
+    pub fn all(frag_count: usize) -> Self {
+        let mut out = Self::none(frag_count);
+        out.words.fill(u64::MAX);
+        out.mask();
+        out
+    }
+
+    /// clears every bit past `len`, so they can't make equal filters unequal.
+    fn mask(&mut self) {
+        let extra = self.words.len() * Self::BITS - self.len;
+        if let Some(last) = self.words.last_mut() {
+            *last &= u64::MAX >> extra;
+        }
+    }
+
+    /// how many fragments this filter knows about, allowed or not.
+    pub fn frag_count(&self) -> usize {
+        self.len
     }
 
     pub fn allows(&self, idx: usize) -> bool {
-        self.0.get(idx).copied().unwrap_or(false)
+        self.words
+            .get(idx / Self::BITS)
+            .is_some_and(|word| word >> (idx % Self::BITS) & 1 == 1)
+    }
+
+    /// allows a fragment, making room for it if necessary.
+    pub fn allow(&mut self, idx: usize) {
+        self.len = self.len.max(idx + 1);
+        self.words.resize(self.len.div_ceil(Self::BITS), 0);
+        self.words[idx / Self::BITS] |= 1 << (idx % Self::BITS);
+    }
+
+    pub fn forbid(&mut self, idx: usize) {
+        if let Some(word) = self.words.get_mut(idx / Self::BITS) {
+            *word &= !(1 << (idx % Self::BITS));
+        }
+    }
+
+    pub fn with(mut self, idx: usize) -> Self {
+        self.allow(idx);
+        self
     }
 
-    fn without(mut self, idx: usize) -> Self {
-        self.0[idx] = false;
+    pub fn without(mut self, idx: usize) -> Self {
+        self.forbid(idx);
         self
     }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let len = self.len.max(other.len);
+        let word = |filter: &Self, i| filter.words.get(i).copied().unwrap_or(0);
+        Self {
+            len,
+            words: (0..len.div_ceil(Self::BITS))
+                .map(|i| f(word(self, i), word(other, i)))
+                .collect(),
+        }
+    }
+
+    /// every fragment allowed by either filter.
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    /// every fragment allowed by both filters.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    /// every fragment allowed by this filter, but not the other.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    /// iterates through every allowed fragment id, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|idx| self.allows(*idx))
+    }
+
+    /// parses a filter from a comma separated list of fragment names.
+    ///
+    /// - `Name` allows a fragment
+    /// - `!Name` forbids a fragment
+    /// - `*` allows every fragment
+    /// - `#3` refers to a fragment by id, in case it has no name
+    ///
+    /// terms are applied from left to right,
+    /// so `*, !Rotor` allows everything except rotors.
+    pub fn parse(text: &str, fragments: &FragmentRegistry) -> Result<Self, FilterError> {
+        let mut out = Self::none(fragments.len());
+        for term in text.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            if term == "*" {
+                out = out.union(&Self::all(fragments.len()));
+                continue;
+            }
+            let (allow, name) = match term.strip_prefix('!') {
+                Some(name) => (false, name.trim()),
+                None => (true, term),
+            };
+            let idx = match name.strip_prefix('#') {
+                Some(id) => id.parse().ok().filter(|id| *id < fragments.len()),
+                None => fragments.id(name),
+            }
+            .ok_or_else(|| FilterError::UnknownFragment(name.to_owned()))?;
+
+            if allow {
+                out.allow(idx);
+            } else {
+                out.forbid(idx);
+            }
+        }
+        Ok(out)
+    }
+
+    /// displays a filter in the same form that `parse` accepts.
+    pub fn display<'a>(&'a self, fragments: &'a FragmentRegistry) -> impl Display + 'a {
+        FilterDisplay {
+            filter: self,
+            fragments,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct FragmentData {
-    names: Vec<String>,
-    behaviors: Vec<Behavior>,
+struct FilterDisplay<'a> {
+    filter: &'a Filter,
+    fragments: &'a FragmentRegistry,
 }
 
-impl FragmentData {
-    pub fn names(&self) -> &[String] {
-        &self.names
+impl Display for FilterDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { filter, fragments } = self;
+        let allowed = filter.iter().count();
+        // list whichever is shorter
+        let excluding = allowed * 2 > filter.len;
+        let mut first = true;
+        if excluding {
+            write!(f, "*")?;
+            first = false;
+        }
+        for idx in (0..filter.len).filter(|idx| filter.allows(*idx) != excluding) {
+            if !first {
+                write!(f, ", ")?;
+            }
+            first = false;
+            if excluding {
+                write!(f, "!")?;
+            }
+            match fragments.name(idx).filter(|name| !name.is_empty()) {
+                Some(name) => write!(f, "{name}")?,
+                None => write!(f, "#{idx}")?,
+            }
+        }
+        Ok(())
     }
+}
 
-    pub fn behaviors(&self) -> &[Behavior] {
-        &self.behaviors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    UnknownFragment(String),
+    /// a saved filter doesn't have one bit per fragment.
+    WrongLength {
+        len: usize,
+        words: usize,
+    },
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownFragment(name) => write!(f, "unknown fragment: {name:?}"),
+            Self::WrongLength { len, words } => {
+                write!(f, "a filter of {len} fragments can't have {words} words")
+            }
+        }
     }
 }
 
+impl std::error::Error for FilterError {}
+
+/// a planet as it's written in a file, such as `assets/planets/xyyy.ron`.
+///
+/// only the planet and its fragments are read from files so far,
+/// biomes are still built in code.
+#[derive(Debug, Serialize, Deserialize)]
+struct PlanetFile {
+    name: String,
+    desc: String,
+    fragments: Vec<FragmentFile>,
+}
+
+/// a fragment as it's written in a file.
+///
+/// compositions name the fragments they're made of,
+/// since ids depend on where each fragment is in the list.
+#[derive(Debug, Serialize, Deserialize)]
+struct FragmentFile {
+    name: String,
+    #[serde(default)]
+    desc: String,
+    appearance: Appearance,
+    composition: [(String, Orient); 4],
+    #[serde(default)]
+    behavior: Behavior,
+}
+
 #[derive(Debug)]
 pub struct Planet {
     // icon: Icon,
     name: String,
     desc: String,
-    fragments: FragmentData,
+    fragments: FragmentRegistry,
     biomes: BiomeCache,
 }
 
 impl Planet {
     /// TODO: FOR TESTING PURPOSES
     pub fn new_xyyy() -> Self {
-        let xyyy = [
-            ("", vec![]),
-            ("X", vec![]),
-            ("Y", vec![]),
-            ("Flip-Flop", flip_self_and_below_self()),
-            ("Spinner", hexagon()),
-            ("Rotor", rotate()),
-            ("Grower", grow()),
-            ("Sucker", suck()),
-            ("Wire", wire()),
-        ];
-        let frag_count = xyyy.len();
-        let mut names = Vec::with_capacity(frag_count);
-        let mut behaviors = Vec::with_capacity(frag_count);
-        for (name, behavior) in xyyy {
-            names.push(name.to_string());
-            behaviors.push(behavior);
-        }
+        let mut planet = Self::from_ron(include_str!("../../../../assets/planets/xyyy.ron"))
+            .expect("xyyy.ron is a valid planet");
+        planet.biomes = BiomeCache::new_xyyy(&planet.fragments);
+        planet
+    }
 
-        Self {
-            name: "XYYY".into(),
-            desc: "The first planet.".into(),
-            fragments: FragmentData { names, behaviors },
-            biomes: BiomeCache::new_xyyy(frag_count),
+    /// reads a planet from a file written like `assets/planets/xyyy.ron`.
+    /// biomes aren't in planet files yet, so it starts out without any.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    fn from_ron(text: &str) -> io::Result<Self> {
+        let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+        let file: PlanetFile = ron::from_str(text).map_err(|error| invalid(error.to_string()))?;
+
+        // compositions may refer to fragments that come after them,
+        // so ids are found by their position in the list instead of the registry
+        let id = |name: &str| {
+            let pos = file.fragments.iter().position(|frag| frag.name == name);
+            pos.map(|pos| pos + 1)
+                .ok_or_else(|| invalid(format!("there is no fragment named {name:?}")))
+        };
+        let mut fragments = FragmentRegistry::new();
+        for frag in &file.fragments {
+            let mut composition = Quad([Tile::SPACE; 4]);
+            for (tile, (name, orient)) in composition.0.iter_mut().zip(&frag.composition) {
+                *tile = Tile {
+                    id: id(name)?,
+                    orient: *orient,
+                };
+            }
+            fragments
+                .register(Fragment {
+                    name: frag.name.clone(),
+                    desc: frag.desc.clone(),
+                    composition,
+                    behavior: frag.behavior.clone(),
+                    appearance: frag.appearance.clone(),
+                })
+                .map_err(|error| invalid(error.to_string()))?;
         }
+        fragments
+            .build_fractal()
+            .map_err(|error| invalid(error.to_string()))?;
+
+        Ok(Self {
+            name: file.name,
+            desc: file.desc,
+            fragments,
+            biomes: BiomeCache::default(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    pub fn fragments(&self) -> &FragmentData {
+    /// doesn't change the id it's cached under.
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub fn desc(&self) -> &str {
+        &self.desc
+    }
+
+    pub fn fragments(&self) -> &FragmentRegistry {
         &self.fragments
     }
 
+    /// fractals built before an edit won't know about it.
+    pub fn fragments_mut(&mut self) -> &mut FragmentRegistry {
+        &mut self.fragments
+    }
+
     pub fn biomes(&self) -> &BiomeCache {
         &self.biomes
     }
@@ -109,77 +384,126 @@ pub struct Biome {
     name: String,
     desc: String,
     fragment_filter: Filter,
+    collision_policy: CollisionPolicy,
+    missions: Vec<Mission>,
 }
 
 impl Biome {
     /// TODO: FOR TESTING PURPOSES
-    pub fn new_xyyy_spinless(frag_count: usize) -> Self {
+    pub fn new_xyyy_spinless(fragments: &FragmentRegistry) -> Self {
+        let id = |name| fragments.id(name).unwrap();
         Self {
             name: "Spinless".into(),
             desc: "Disables rotors and spinners.".into(),
-            fragment_filter: Filter::all(frag_count)
-                .without(Tile::ROTOR.id)
-                .without(Tile::W.id),
+            fragment_filter: Filter::all(fragments.len())
+                .without(id("Rotor"))
+                .without(id("Spinner")),
+            collision_policy: CollisionPolicy::Cancel,
+            missions: vec![Mission {
+                name: "Solid Ground".into(),
+                desc: "Build a triangle out of 4 Ys.".into(),
+                goals: vec![Goal::Pattern {
+                    depth: 3,
+                    quad: Quad([fragments.tile("Y").unwrap(); 4]),
+                }],
+            }],
         }
     }
 
     /// TODO: FOR TESTING PURPOSES
-    pub fn new_xyyy_landing_zone(frag_count: usize) -> Self {
+    pub fn new_xyyy_landing_zone(fragments: &FragmentRegistry) -> Self {
         Self {
             name: "Landing Zone".into(),
             desc: "Contains every fragment.".into(),
-            fragment_filter: Filter::all(frag_count),
+            fragment_filter: Filter::all(fragments.len()),
+            collision_policy: CollisionPolicy::Cancel,
+            missions: vec![Mission {
+                name: "First Harvest".into(),
+                desc: "Store 100 Ys.".into(),
+                goals: vec![Goal::Store {
+                    fragment: fragments.id("Y").unwrap(),
+                    count: 100,
+                }],
+            }],
+        }
+    }
+
+    /// TODO: FOR TESTING PURPOSES
+    pub fn new_xyyy_bulldozer(fragments: &FragmentRegistry) -> Self {
+        Self {
+            name: "Bulldozer".into(),
+            desc: "Moving into an occupied tile pushes it out of the way.".into(),
+            fragment_filter: Filter::all(fragments.len()),
+            collision_policy: CollisionPolicy::Push,
+            missions: vec![Mission {
+                name: "Perpetual Motion".into(),
+                desc: "Keep the factory running for 1000 ticks without anything getting stuck."
+                    .into(),
+                goals: vec![Goal::RunFor { ticks: 1000 }],
+            }],
         }
     }
 
     pub fn leaf_count(&self) -> usize {
-        self.fragment_filter.0.len()
+        self.fragment_filter.frag_count()
     }
 
     pub fn default_id(&self) -> BiomeId {
         BiomeId(self.name.clone().into())
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn desc(&self) -> &str {
+        &self.desc
+    }
+
     pub fn fragment_filter(&self) -> &Filter {
         &self.fragment_filter
     }
 
-    pub fn behavior<'a>(&self, behaviors: &'a [Behavior], id: usize) -> &'a Behavior {
+    pub fn set_fragment_filter(&mut self, filter: Filter) {
+        self.fragment_filter = filter;
+    }
+
+    pub fn collision_policy(&self) -> CollisionPolicy {
+        self.collision_policy
+    }
+
+    pub fn missions(&self) -> &[Mission] {
+        &self.missions
+    }
+
+    pub fn behavior<'a>(&self, fragments: &'a FragmentRegistry, id: usize) -> &'a Behavior {
         const EMPTY: &Behavior = &vec![];
         self.fragment_filter
             .allows(id)
             .then_some(())
-            .and_then(|()| behaviors.get(id))
+            .and_then(|()| fragments.behavior(id))
             .unwrap_or(EMPTY)
     }
 }
 
-#[derive(Debug)]
-pub struct Fragment {
-    // icon: Icon,
-    name: String,
-    desc: String,
-    behavior: Behavior,
-}
-
 // TODO: FOR TESTING PURPOSES
 fn swap_01_with_10() -> Behavior {
     vec![
-        TargetedAction {
-            target: TileOffset {
+        TargetedAction::new(
+            TileOffset {
                 depth: 0,
                 offset: IVec2 { x: 1, y: 0 },
                 flop: false,
             },
-            act: TileAction::Store,
-        },
-        TargetedAction {
-            target: TileOffset {
+            TileAction::Store,
+        ),
+        TargetedAction::new(
+            TileOffset {
                 depth: 0,
                 offset: IVec2 { x: 0, y: 1 },
                 flop: false,
             },
-            act: TileAction::Move(
+            TileAction::Move(
                 TileOffset {
                     depth: 0,
                     offset: IVec2 { x: 1, y: 0 },
@@ -187,132 +511,7 @@ fn swap_01_with_10() -> Behavior {
                 },
                 Transform::KU,
             ),
-        },
-    ]
-}
-
-fn flip_self_and_below_self() -> Behavior {
-    let this = TileOffset::ZERO;
-    let below = TileOffset {
-        depth: 0,
-        offset: IVec2::ZERO,
-        flop: true,
-    };
-    vec![
-        TargetedAction {
-            target: this,
-            act: TileAction::Move(this, Transform::FU),
-        },
-        TargetedAction {
-            target: below,
-            act: TileAction::Move(below, Transform::FU),
-        },
-    ]
-}
-
-fn hexagon() -> Behavior {
-    let this = TileOffset::ZERO;
-    let below = TileOffset {
-        depth: 0,
-        offset: IVec2::ZERO,
-        flop: true,
-    };
-    vec![
-        TargetedAction {
-            target: this,
-            act: TileAction::Move(below, Transform::KR),
-        },
-        TargetedAction {
-            target: below,
-            act: TileAction::Activate,
-        },
-    ]
-}
-
-fn rotate() -> Behavior {
-    let this = TileOffset::ZERO;
-    let u = TileOffset {
-        depth: 0,
-        offset: IVec2::ZERO,
-        flop: true,
-    };
-    let l = TileOffset {
-        depth: 0,
-        offset: IVec2::new(0, -1),
-        flop: true,
-    };
-    let r = TileOffset {
-        depth: 0,
-        offset: IVec2::new(-1, -1),
-        flop: true,
-    };
-    vec![
-        TargetedAction {
-            target: u,
-            act: TileAction::Move(r, Transform::KR),
-        },
-        TargetedAction {
-            target: r,
-            act: TileAction::Move(l, Transform::KR),
-        },
-        TargetedAction {
-            target: l,
-            act: TileAction::Move(u, Transform::KR),
-        },
-        TargetedAction {
-            target: this,
-            act: TileAction::Activate,
-        },
-    ]
-}
-
-fn grow() -> Behavior {
-    let below = TileOffset {
-        depth: 0,
-        offset: IVec2::ZERO,
-        flop: true,
-    };
-    let center_below = TileOffset {
-        depth: 1,
-        offset: IVec2::new(1, 2),
-        flop: false,
-    };
-    vec![TargetedAction {
-        target: center_below,
-        act: TileAction::Move(below, Transform::KU),
-    }]
-}
-
-fn suck() -> Behavior {
-    let below = TileOffset {
-        depth: 0,
-        offset: IVec2::ZERO,
-        flop: true,
-    };
-    vec![TargetedAction {
-        target: below,
-        act: TileAction::Store,
-    }]
-}
-
-fn wire() -> Behavior {
-    vec![
-        TargetedAction {
-            target: TileOffset {
-                depth: 0,
-                offset: IVec2::new(0, -1),
-                flop: true,
-            },
-            act: TileAction::Activate,
-        },
-        TargetedAction {
-            target: TileOffset {
-                depth: 0,
-                offset: IVec2::new(-1, -1),
-                flop: true,
-            },
-            act: TileAction::Activate,
-        },
+        ),
     ]
 }
 
@@ -324,12 +523,13 @@ pub struct BiomeCache {
 
 impl BiomeCache {
     /// TODO: FOR TESTING PURPOSES
-    fn new_xyyy(frag_count: usize) -> Self {
+    fn new_xyyy(fragments: &FragmentRegistry) -> Self {
         Self {
             biomes: HashMap::from(
                 [
-                    Biome::new_xyyy_spinless(frag_count),
-                    Biome::new_xyyy_landing_zone(frag_count),
+                    Biome::new_xyyy_spinless(fragments),
+                    Biome::new_xyyy_landing_zone(fragments),
+                    Biome::new_xyyy_bulldozer(fragments),
                 ]
                 .map(|b| (b.default_id(), b)),
             ),
@@ -345,8 +545,12 @@ impl BiomeCache {
         self.biomes.insert(id, biome);
     }
 
-    pub fn load(&mut self, id: BiomeId) -> std::io::Result<&Biome> {
-        todo!("load a new biome");
+    /// biomes are only built in code for now, so this only fails.
+    pub fn load(&mut self, id: BiomeId) -> io::Result<&Biome> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("biome {id:?} is not loaded, and biomes can't be read from files yet"),
+        ))
     }
 
     pub fn get_or_load(&mut self, id: BiomeId) -> std::io::Result<&Biome> {
@@ -360,11 +564,21 @@ impl BiomeCache {
     pub fn get(&self, id: &BiomeId) -> Option<&Biome> {
         self.biomes.get(id)
     }
+
+    pub fn get_mut(&mut self, id: &BiomeId) -> Option<&mut Biome> {
+        self.biomes.get_mut(id)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BiomeId(Rc<str>);
 
+impl BiomeId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 impl<T: Into<Rc<str>>> From<T> for BiomeId {
     fn from(value: T) -> Self {
         Self(value.into())
@@ -374,16 +588,39 @@ impl<T: Into<Rc<str>>> From<T> for BiomeId {
 #[derive(Debug, Default)]
 pub struct PlanetCache {
     planets: HashMap<PlanetId, Planet>,
+    /// where to look for planets that aren't loaded yet
+    dir: Option<PathBuf>,
 }
 
 impl PlanetCache {
+    /// a cache that loads planets from `dir`, such as `assets/planets`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            planets: HashMap::new(),
+            dir: Some(dir.into()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PlanetId> {
+        self.planets.keys()
+    }
+
     /// inserts a new biomeid-biome pair into the cache.
     pub fn register(&mut self, id: PlanetId, planet: Planet) {
         self.planets.insert(id, planet);
     }
 
-    pub fn load(&mut self, id: PlanetId) -> std::io::Result<&mut Planet> {
-        todo!("load a new biome");
+    /// reads a planet from its file, named after its id in lowercase, such as `xyyy.ron`.
+    /// fails if this cache wasn't given a folder to look in.
+    pub fn load(&mut self, id: PlanetId) -> io::Result<&mut Planet> {
+        let Some(dir) = &self.dir else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("planet {id:?} is not loaded, and there's no folder to load it from"),
+            ));
+        };
+        let planet = Planet::load(dir.join(format!("{}.ron", id.as_str().to_lowercase())))?;
+        Ok(self.planets.entry(id).or_insert(planet))
     }
 
     pub fn get_or_load(&mut self, id: PlanetId) -> std::io::Result<&mut Planet> {
@@ -406,3 +643,15 @@ impl PlanetCache {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PlanetId(Rc<str>);
+
+impl PlanetId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<T: Into<Rc<str>>> From<T> for PlanetId {
+    fn from(value: T) -> Self {
+        Self(value.into())
+    }
+}
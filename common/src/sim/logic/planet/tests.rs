@@ -0,0 +1,175 @@
+use super::*;
+use crate::sim::logic::orientation::Symmetries;
+
+/// a registry with just enough named fragments to test filters
+fn fragments() -> FragmentRegistry {
+    let mut fragments = FragmentRegistry::new();
+    for name in ["X", "Y", "Flip-Flop", "Spinner", "Rotor"] {
+        fragments
+            .register(Fragment {
+                name: name.into(),
+                desc: String::new(),
+                composition: Quad::SPACE,
+                behavior: vec![],
+                appearance: Appearance::default(),
+            })
+            .unwrap();
+    }
+    fragments
+}
+
+#[test]
+fn test_filter_bits() {
+    let filter = Filter::all(70).without(3).without(64);
+    assert_eq!(filter.frag_count(), 70);
+    assert!(filter.allows(0));
+    assert!(!filter.allows(3));
+    assert!(!filter.allows(64));
+    assert!(filter.allows(69));
+    assert!(!filter.allows(70));
+    assert_eq!(filter.iter().count(), 68);
+
+    let filter = Filter::none(2).with(100);
+    assert_eq!(filter.frag_count(), 101);
+    assert_eq!(filter.iter().collect::<Vec<_>>(), [100]);
+}
+
+#[test]
+fn test_filter_saved() {
+    let raw = |len, words: &[u64]| RawFilter {
+        len,
+        words: words.to_vec(),
+    };
+    assert_eq!(
+        Filter::try_from(raw(70, &[u64::MAX, 0b1_1111])),
+        Ok(Filter::all(70).without(69))
+    );
+    // bits past the end are dropped, so they don't affect equality
+    assert_eq!(Filter::try_from(raw(2, &[u64::MAX])), Ok(Filter::all(2)));
+    assert_eq!(Filter::try_from(raw(0, &[])), Ok(Filter::none(0)));
+
+    let wrong_length = FilterError::WrongLength { len: 70, words: 1 };
+    assert_eq!(Filter::try_from(raw(70, &[0])), Err(wrong_length));
+    let wrong_length = FilterError::WrongLength { len: 2, words: 2 };
+    assert_eq!(Filter::try_from(raw(2, &[0, 0])), Err(wrong_length));
+}
+
+#[test]
+fn test_filter_algebra() {
+    let a = Filter::none(6).with(1).with(2).with(3);
+    let b = Filter::none(6).with(3).with(4);
+    let ids = |filter: Filter| filter.iter().collect::<Vec<_>>();
+    assert_eq!(ids(a.union(&b)), [1, 2, 3, 4]);
+    assert_eq!(ids(a.intersection(&b)), [3]);
+    assert_eq!(ids(a.difference(&b)), [1, 2]);
+    assert_eq!(ids(b.difference(&a)), [4]);
+
+    // filters of different sizes
+    let c = Filter::all(2);
+    assert_eq!(ids(c.union(&b)), [0, 1, 3, 4]);
+    assert_eq!(ids(b.difference(&c)), [3, 4]);
+}
+
+#[test]
+fn test_filter_parse() {
+    let fragments = fragments();
+    let parse = |text| Filter::parse(text, &fragments).map(|f| f.iter().collect::<Vec<_>>());
+    assert_eq!(parse(""), Ok(vec![]));
+    assert_eq!(parse("X, Flip-Flop"), Ok(vec![1, 3]));
+    assert_eq!(parse("*, !Rotor, !Spinner"), Ok(vec![0, 1, 2, 3]));
+    assert_eq!(parse("#0, Y"), Ok(vec![0, 2]));
+    assert_eq!(
+        parse("X, Z"),
+        Err(FilterError::UnknownFragment("Z".to_owned()))
+    );
+    assert_eq!(
+        parse("#6"),
+        Err(FilterError::UnknownFragment("#6".to_owned()))
+    );
+}
+
+#[test]
+fn test_filter_display() {
+    let fragments = fragments();
+    let cases = [
+        (Filter::none(6), ""),
+        (Filter::all(6), "*"),
+        (Filter::none(6).with(1).with(4), "X, Spinner"),
+        (Filter::all(6).without(0).without(5), "*, !#0, !Rotor"),
+    ];
+    for (filter, text) in cases {
+        assert_eq!(filter.display(&fragments).to_string(), text);
+        assert_eq!(Filter::parse(text, &fragments), Ok(filter));
+    }
+}
+
+#[test]
+fn test_xyyy_fragments() {
+    let planet = Planet::new_xyyy();
+    let fragments = planet.fragments();
+    assert_eq!(fragments.len(), 9);
+    assert_eq!(fragments.id(""), Some(0));
+    assert_eq!(fragments.id("Rotor"), Some(5));
+    assert_eq!(fragments.id("Z"), None);
+
+    use Symmetries::*;
+    let symmetries = fragments
+        .iter()
+        .map(|(id, _)| fragments.symmetries(id).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symmetries,
+        [
+            Isotropic, Isotropic, Isotropic, Reflective, Asymmetric, Rotational, Reflective,
+            Reflective, Reflective
+        ]
+    );
+
+    let fractal = fragments.build_fractal().unwrap();
+    for (id, fragment) in fragments.iter() {
+        assert_eq!(fractal.library[id].quad, fragment.composition);
+    }
+}
+
+#[test]
+fn test_load() {
+    let mut planets = PlanetCache::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/planets"));
+    let loaded = planets.load(PlanetId::from("XYYY")).unwrap();
+    assert_eq!(loaded.name(), "XYYY");
+    assert_eq!(loaded.biomes().iter().count(), 0);
+
+    let xyyy = Planet::new_xyyy();
+    let fragments = |planet: &Planet| {
+        let fragments = planet.fragments();
+        let fragments = fragments.iter().map(|(_, fragment)| {
+            let Fragment {
+                name,
+                desc,
+                composition,
+                behavior,
+                appearance,
+            } = fragment.clone();
+            (name, desc, composition, behavior.len(), appearance)
+        });
+        fragments.collect::<Vec<_>>()
+    };
+    assert_eq!(fragments(loaded), fragments(&xyyy));
+    let x = xyyy.fragments().by_name("X").unwrap();
+    assert_eq!(x.appearance.sprite.as_deref(), Some("sprites/boccs.png"));
+
+    // planets and fragments that aren't there
+    let error = planets.load(PlanetId::from("Nowhere")).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    let error = PlanetCache::default()
+        .load(PlanetId::from("XYYY"))
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    let text = r#"(name: "Z", desc: "", fragments: [(
+        name: "Z",
+        appearance: (symbol: 'Z'),
+        composition: (("Z", Iso), ("Z", Iso), ("Z", Iso), ("W", Iso)),
+    )])"#;
+    let error = Planet::from_ron(text).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("\"W\""), "{error}");
+}
@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod tests;
+
+use super::{
+    command::{Command, CommandError, CommandHistory},
+    factory::FractoryMeta,
+    fragment::FragmentRegistry,
+    orientation::Orient,
+    planet::{Biome, BiomeId, PlanetId},
+    tile::Tile,
+};
+use std::{fmt::Display, str::FromStr};
+
+/// a recording of every command applied to a fractory,
+/// for reproducing a session exactly.
+///
+/// scheduled ticks aren't commands, so they're only recorded as the tick count
+/// each command happened at.
+///
+/// saved as text, one command per line:
+/// ```txt
+/// fractory replay
+/// planet xyyy
+/// biome Landing Zone
+/// start 49 AKU
+/// 0 toggle d3:1,2f
+/// 0 tick
+/// 4 rotate d2:0,1 cw
+/// ticks 7
+/// end 74 AKU
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub planet: PlanetId,
+    pub biome: BiomeId,
+    /// the root tile before the first command
+    pub start: Tile,
+    /// every command, along with how many ticks had happened before it
+    pub entries: Vec<(u64, Command)>,
+    /// how many ticks have been recorded, scheduled or not
+    pub ticks: u64,
+    /// the root tile after the last command, once the recording is finished
+    pub end: Option<Tile>,
+}
+
+/// why a replay couldn't be read or played back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// a line of the file doesn't make sense; lines are counted from 1
+    Parse { line: usize, text: String },
+    /// the fractory doesn't start out the way the recording did
+    StartMismatch { expected: Tile, found: Tile },
+    /// a command failed, even though it worked while recording
+    Command { index: usize, error: CommandError },
+    /// the fractory didn't end up the way the recording did
+    EndMismatch { expected: Tile, found: Tile },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse { line, text } => write!(f, "line {line} is not valid: {text:?}"),
+            Self::StartMismatch { expected, found } => {
+                write!(f, "expected to start at {expected:?}, found {found:?}")
+            }
+            Self::Command { index, error } => write!(f, "command #{index} failed: {error}"),
+            Self::EndMismatch { expected, found } => {
+                write!(f, "expected to end at {expected:?}, found {found:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// starts recording a fractory as it is right now.
+    ///
+    /// clears `history`, since playback starts with nothing to undo,
+    /// so undoing a command from before the recording couldn't be reproduced.
+    pub fn new(meta: &FractoryMeta, history: &mut CommandHistory) -> Self {
+        history.clear();
+        Self {
+            planet: meta.planet.clone(),
+            biome: meta.biome.clone(),
+            start: meta.fractory.fractal.root,
+            entries: vec![],
+            ticks: 0,
+            end: None,
+        }
+    }
+
+    /// records a command that was just executed successfully.
+    pub fn record(&mut self, command: Command) {
+        self.entries.push((self.ticks, command));
+        if command == Command::Tick {
+            self.ticks += 1;
+        }
+    }
+
+    /// the earliest tick the next command could have happened at.
+    fn tick_after_entries(&self) -> u64 {
+        self.entries.last().map_or(0, |&(tick, command)| {
+            tick + (command == Command::Tick) as u64
+        })
+    }
+
+    /// records a tick that happened on its own, without a command.
+    pub fn record_tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// remembers how the fractory ended up, so playback can be verified.
+    pub fn finish(&mut self, meta: &FractoryMeta) {
+        self.end = Some(meta.fractory.fractal.root);
+    }
+
+    /// plays back every command on a fractory that starts out like the recorded one,
+    /// then makes sure it ended up the same way.
+    pub fn play(
+        &self,
+        meta: &mut FractoryMeta,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+    ) -> Result<(), ReplayError> {
+        self.play_until(meta, fragments, biome, u64::MAX)?;
+        let found = meta.fractory.fractal.root;
+        match self.end {
+            Some(expected) if expected != found => {
+                Err(ReplayError::EndMismatch { expected, found })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// plays back every command and tick up until `ticks` ticks have happened.
+    pub fn play_until(
+        &self,
+        meta: &mut FractoryMeta,
+        fragments: &FragmentRegistry,
+        biome: &Biome,
+        ticks: u64,
+    ) -> Result<(), ReplayError> {
+        let found = meta.fractory.fractal.root;
+        if found != self.start {
+            return Err(ReplayError::StartMismatch {
+                expected: self.start,
+                found,
+            });
+        }
+
+        // scheduled ticks don't go through the history, just like in `crate::run`
+        let mut history = CommandHistory::default();
+        let mut ticked = 0;
+        for (index, &(tick, command)) in self.entries.iter().enumerate() {
+            while ticked < tick.min(ticks) {
+                meta.tick(fragments, biome);
+                ticked += 1;
+            }
+            if tick >= ticks {
+                return Ok(());
+            }
+            meta.execute(command, fragments, biome, &mut history)
+                .map_err(|error| ReplayError::Command { index, error })?;
+            if command == Command::Tick {
+                ticked += 1;
+            }
+        }
+        while ticked < self.ticks.min(ticks) {
+            meta.tick(fragments, biome);
+            ticked += 1;
+        }
+        Ok(())
+    }
+}
+
+fn parse_tile<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Tile> {
+    let id = words.next()?.parse().ok()?;
+    let orient = words.next()?;
+    let orient = Orient::ORIENTATIONS
+        .into_iter()
+        .find(|o| format!("{o:?}") == orient)?;
+    Some(Tile { id, orient })
+}
+
+fn parse_command(line: &str) -> Option<(u64, Command)> {
+    let mut words = line.split_whitespace();
+    let tick = words.next()?.parse().ok()?;
+    let command = match words.next()? {
+        "tick" => Command::Tick,
+        "undo" => Command::Undo,
+        "set" => Command::SetTile(words.next()?.parse().ok()?, parse_tile(&mut words)?),
+        "rotate" => Command::Rotate {
+            pos: words.next()?.parse().ok()?,
+            clockwise: match words.next()? {
+                "cw" => true,
+                "ccw" => false,
+                _ => return None,
+            },
+        },
+        "flip" => Command::Flip(words.next()?.parse().ok()?),
+        "toggle" => Command::ToggleActive(words.next()?.parse().ok()?),
+        "store" => Command::Store(words.next()?.parse().ok()?),
+        "place" => Command::Place(words.next()?.parse().ok()?, words.next()?.parse().ok()?),
+        _ => return None,
+    };
+    words.next().is_none().then_some((tick, command))
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "fractory replay")?;
+        writeln!(f, "planet {}", self.planet.as_str())?;
+        writeln!(f, "biome {}", self.biome.as_str())?;
+        writeln!(f, "start {} {:?}", self.start.id, self.start.orient)?;
+        for &(tick, command) in &self.entries {
+            write!(f, "{tick} ")?;
+            match command {
+                Command::Tick => write!(f, "tick")?,
+                Command::Undo => write!(f, "undo")?,
+                Command::SetTile(pos, tile) => {
+                    write!(f, "set {pos:#} {} {:?}", tile.id, tile.orient)?
+                }
+                Command::Rotate { pos, clockwise } => {
+                    let direction = if clockwise { "cw" } else { "ccw" };
+                    write!(f, "rotate {pos:#} {direction}")?
+                }
+                Command::Flip(pos) => write!(f, "flip {pos:#}")?,
+                Command::ToggleActive(pos) => write!(f, "toggle {pos:#}")?,
+                Command::Store(pos) => write!(f, "store {pos:#}")?,
+                Command::Place(pos, id) => write!(f, "place {pos:#} {id}")?,
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "ticks {}", self.ticks)?;
+        if let Some(end) = self.end {
+            writeln!(f, "end {} {:?}", end.id, end.orient)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().collect::<Vec<_>>();
+        let lines_len = lines.len();
+        let error = |i: usize| ReplayError::Parse {
+            line: i + 1,
+            text: lines.get(i).copied().unwrap_or_default().to_owned(),
+        };
+        let header = |i: usize, prefix: &str| {
+            let line = lines.get(i).copied().unwrap_or_default();
+            line.strip_prefix(prefix).ok_or_else(|| error(i))
+        };
+
+        if !header(0, "fractory replay")?.is_empty() {
+            return Err(error(0));
+        }
+        let mut out = Self {
+            planet: header(1, "planet ")?.into(),
+            biome: header(2, "biome ")?.into(),
+            start: parse_tile(header(3, "start ")?.split_whitespace()).ok_or_else(|| error(3))?,
+            entries: vec![],
+            ticks: 0,
+            end: None,
+        };
+        let mut lines = lines
+            .iter()
+            .enumerate()
+            .skip(4)
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        // commands, until the tick count
+        loop {
+            let Some((i, line)) = lines.next() else {
+                return Err(error(lines_len));
+            };
+            if let Some(ticks) = line.strip_prefix("ticks ") {
+                out.ticks = ticks.parse().map_err(|_| error(i))?;
+                if out.ticks < out.tick_after_entries() {
+                    return Err(error(i));
+                }
+                break;
+            }
+            let (tick, command) = parse_command(line).ok_or_else(|| error(i))?;
+            if tick < out.tick_after_entries() {
+                return Err(error(i));
+            }
+            out.entries.push((tick, command));
+        }
+
+        if let Some((i, line)) = lines.next() {
+            let end = line.strip_prefix("end ").ok_or_else(|| error(i))?;
+            out.end = Some(parse_tile(end.split_whitespace()).ok_or_else(|| error(i))?);
+        }
+        if let Some((i, _)) = lines.next() {
+            return Err(error(i));
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,139 @@
+use super::*;
+use crate::sim::logic::{path::TilePos, planet::PlanetCache};
+
+fn pos(x: i32, y: i32) -> TilePos {
+    format!("d2:{x},{y}").parse().unwrap()
+}
+
+/// records a short session, with scheduled ticks in between commands.
+fn record(planets: &mut PlanetCache) -> (Replay, FractoryMeta) {
+    let mut meta = FractoryMeta::new_xyyy(planets);
+    let planet = planets.get(&meta.planet).unwrap();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+    let spinner = fragments.tile("Spinner").unwrap();
+
+    let mut history = CommandHistory::default();
+    let mut replay = Replay::new(&meta, &mut history);
+    let commands = [
+        Command::SetTile(pos(0, 3), spinner),
+        Command::ToggleActive(pos(0, 3)),
+        Command::Tick,
+        Command::Rotate {
+            pos: pos(0, 3),
+            clockwise: false,
+        },
+        Command::Undo,
+        Command::Flip(pos(1, 3)),
+    ];
+    for command in commands {
+        meta.execute(command, fragments, biome, &mut history)
+            .unwrap();
+        replay.record(command);
+        meta.tick(fragments, biome);
+        replay.record_tick();
+    }
+    replay.finish(&meta);
+    (replay, meta)
+}
+
+#[test]
+fn test_play() {
+    let mut planets = PlanetCache::default();
+    let (replay, recorded) = record(&mut planets);
+    assert_eq!(replay.ticks, 7);
+    assert_eq!(replay.entries[3].0, 4);
+
+    let planet = planets.get(&recorded.planet).unwrap();
+    let biome = planet.biomes().get(&recorded.biome).unwrap();
+    let mut meta = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    replay.play(&mut meta, planet.fragments(), biome).unwrap();
+    assert_eq!(meta.fractory.fractal.root, recorded.fractory.fractal.root);
+    assert!(meta
+        .fractory
+        .activated
+        .iter()
+        .eq(recorded.fractory.activated.iter()));
+
+    // the start has to match
+    let err = replay.play(&mut meta, planet.fragments(), biome);
+    assert!(matches!(err, Err(ReplayError::StartMismatch { .. })));
+
+    // and so does the end
+    let mut tampered = replay.clone();
+    tampered.entries.remove(0);
+    let mut meta = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    let err = tampered.play(&mut meta, planet.fragments(), biome);
+    assert!(matches!(err, Err(ReplayError::EndMismatch { .. })));
+}
+
+#[test]
+fn test_play_until() {
+    let mut planets = PlanetCache::default();
+    let (replay, recorded) = record(&mut planets);
+    let planet = planets.get(&recorded.planet).unwrap();
+    let biome = planet.biomes().get(&recorded.biome).unwrap();
+
+    let mut meta = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    replay
+        .play_until(&mut meta, planet.fragments(), biome, 0)
+        .unwrap();
+    assert_eq!(meta.fractory.fractal.root, replay.start);
+
+    // the first command, and the tick after it
+    replay
+        .play_until(&mut meta, planet.fragments(), biome, 1)
+        .unwrap();
+    let mut expected = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    let mut history = CommandHistory::default();
+    expected
+        .execute(replay.entries[0].1, planet.fragments(), biome, &mut history)
+        .unwrap();
+    expected.tick(planet.fragments(), biome);
+    assert_eq!(meta.fractory.fractal.root, expected.fractory.fractal.root);
+}
+
+#[test]
+fn test_undo_before_start() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    let planet = planets.get(&meta.planet).unwrap();
+    let fragments = planet.fragments();
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+
+    let mut history = CommandHistory::default();
+    meta.execute(Command::Flip(pos(1, 3)), fragments, biome, &mut history)
+        .unwrap();
+
+    // the flip happened before recording, so it can't be undone anymore
+    let replay = Replay::new(&meta, &mut history);
+    let undo = meta.execute(Command::Undo, fragments, biome, &mut history);
+    assert_eq!(undo, Err(CommandError::NothingToUndo));
+    assert_eq!(meta.fractory.fractal.root, replay.start);
+}
+
+#[test]
+fn test_text() {
+    let mut planets = PlanetCache::default();
+    let (replay, _) = record(&mut planets);
+    let text = replay.to_string();
+    assert_eq!(text.parse(), Ok(replay.clone()));
+
+    let mut unfinished = replay.clone();
+    unfinished.end = None;
+    assert_eq!(unfinished.to_string().parse(), Ok(unfinished));
+
+    let broken = text.replace(" ccw", " sideways");
+    let line = text.lines().position(|l| l.contains(" ccw")).unwrap() + 1;
+    assert!(matches!(
+        broken.parse::<Replay>(),
+        Err(ReplayError::Parse { line: l, .. }) if l == line
+    ));
+
+    // commands can't go back in time
+    let broken = text.replace("4 rotate", "1 rotate");
+    assert!(broken.parse::<Replay>().is_err());
+
+    let truncated = text.lines().take(5).collect::<Vec<_>>().join("\n");
+    assert!(truncated.parse::<Replay>().is_err());
+}
@@ -1,7 +1,8 @@
 use super::orientation::{Orient, Transform};
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Index, IndexMut, Sub};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Tile {
     pub id: usize,
     pub orient: Orient,
@@ -18,40 +19,6 @@ impl Tile {
         id: 1,
         orient: Orient::Iso,
     };
-
-    // TODO: FOR TESTING
-    pub const X: Self = Self {
-        id: 1,
-        orient: Orient::Iso,
-    };
-    pub const Y: Self = Self {
-        id: 2,
-        orient: Orient::Iso,
-    };
-    pub const Z: Self = Self {
-        id: 3,
-        orient: Orient::RfU,
-    };
-    pub const W: Self = Self {
-        id: 4,
-        orient: Orient::AKU,
-    };
-    pub const ROTOR: Self = Self {
-        id: 5,
-        orient: Orient::RtK,
-    };
-    pub const GROWER: Self = Self {
-        id: 6,
-        orient: Orient::RfU,
-    };
-    pub const SUCKER: Self = Self {
-        id: 7,
-        orient: Orient::RfU,
-    };
-    pub const WIRE: Self = Self {
-        id: 8,
-        orient: Orient::RfU,
-    };
 }
 
 impl AddAssign<Transform> for Tile {
@@ -130,32 +97,20 @@ impl<T> Quad<T> {
     }
 }
 
+impl<T> IntoIterator for Quad<T> {
+    type Item = T;
+    type IntoIter = std::array::IntoIter<T, 4>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
 impl Quad<Tile> {
     pub const SPACE: Self = Self([Tile::SPACE; 4]);
 
     // TODO: FOR TESTING
     pub const ONE: Self = Self([Tile::ONE; 4]);
-
-    // TODO: FOR TESTING
-    pub const X: Self = Self([Tile::X, Tile::Y, Tile::Y, Tile::Y]);
-    pub const Y: Self = Self([Tile::Y, Tile::X, Tile::X, Tile::X]);
-    pub const Z: Self = Self([Tile::X, Tile::X, Tile::Y, Tile::Y]);
-    pub const W: Self = Self([Tile::Z, Tile::X, Tile::Y, Tile::X]);
-    pub const ROTOR: Self = Self([
-        Tile::X,
-        Tile {
-            id: Tile::Z.id,
-            orient: Tile::Z.orient.rot_cw(),
-        },
-        Tile {
-            id: Tile::Z.id,
-            orient: Tile::Z.orient.rot_cw().rot_cw(),
-        },
-        Tile::Z,
-    ]);
-    pub const GROWER: Self = Self([Tile::Z, Tile::X, Tile::Y, Tile::Y]);
-    pub const SUCKER: Self = Self([Tile::Z, Tile::Y, Tile::X, Tile::X]);
-    pub const WIRE: Self = Self([Tile::Y, Tile::Y, Tile::X, Tile::X]);
 }
 
 impl<T> Index<SubTile> for Quad<T> {
@@ -199,9 +154,21 @@ impl Quad<Tile> {
 
     /// reorients a tringle upright, and returns its original orientation.
     pub fn reorient(&mut self) -> Orient {
+        self.reorient_within(&Transform::TRANSFORMS)
+    }
+
+    /// like `reorient`, but only considers symmetries reachable through `transforms`.
+    /// squares can't be rotated, so they pass `[KU, FU]` and count as rotationally symmetric,
+    /// which leaves them as either `Iso` or `RtK`/`RtF`.
+    pub fn reorient_within(&mut self, transforms: &[Transform]) -> Orient {
         use Orient::*;
-        let is_rot = self.is_rotational();
-        for i in 0..3 {
+        let rotations = if transforms.contains(&Transform::KR) {
+            3
+        } else {
+            1
+        };
+        let is_rot = rotations == 1 || self.is_rotational();
+        for i in 0..rotations {
             if self.is_rfu() {
                 return if is_rot { Iso } else { [RfU, RfL, RfR][i] };
             }
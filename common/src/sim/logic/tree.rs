@@ -1,6 +1,9 @@
+#[cfg(test)]
+mod tests;
+
 pub mod collision;
 
-use super::{path::TilePos, tile::Quad};
+use super::{path::Position, tile::Quad};
 use std::fmt::{Debug, Display};
 
 type Index = usize;
@@ -18,7 +21,7 @@ pub enum Node {
 }
 
 impl Node {
-    pub fn create_at(mut path: TilePos, value: Index) -> Self {
+    pub fn create_at(mut path: impl Position, value: Index) -> Self {
         match path.pop_front() {
             Some(subtile) => {
                 // Node does not implement Copy, hardcoding 4 frees is easier.
@@ -46,18 +49,86 @@ impl Node {
 
     /// sets a specified value at a specified path.
     /// calls drop_item if a collision happens.
-    pub fn set(&mut self, mut path: TilePos, value: Index, drop_item: &mut impl FnMut(Index)) {
-        let mut reject = |this: &mut Self| {
-            drop_item(value);
-            this.drop_with(drop_item);
-        };
+    pub fn set(&mut self, path: impl Position, value: Index, drop_item: &mut impl FnMut(Index)) {
+        self.set_ranked(path, value, &|_| false, drop_item)
+    }
 
+    /// sets a specified value at a specified path.
+    ///
+    /// if a collision happens, the new value is dropped,
+    /// along with every value it collided with that doesn't outrank it.
+    /// values that outrank the new value are kept.
+    ///
+    /// assumes values are set from highest to lowest rank,
+    /// so a Bad node (left by values that tied) always outranks the new value.
+    pub fn set_ranked(
+        &mut self,
+        mut path: impl Position,
+        value: Index,
+        outranks: &impl Fn(Index) -> bool,
+        drop_item: &mut impl FnMut(Index),
+    ) {
         match self {
             Node::Free => *self = Self::create_at(path, value),
-            Node::Bad | Node::Leaf(_) => reject(self),
+            Node::Bad => drop_item(value),
+            Node::Leaf(item) => {
+                drop_item(value);
+                if !outranks(*item) {
+                    self.drop_with(drop_item);
+                }
+            }
+            Node::Branch(children) => match path.pop_front() {
+                Some(subtile) => children[subtile].set_ranked(path, value, outranks, drop_item),
+                None => {
+                    drop_item(value);
+                    if self.any(outranks) {
+                        self.drop_outranked(outranks, drop_item);
+                    } else {
+                        self.drop_with(drop_item);
+                    }
+                }
+            },
+        }
+    }
+
+    /// checks whether any value in this node satisfies a predicate.
+    fn any(&self, f: &impl Fn(Index) -> bool) -> bool {
+        match self {
+            Node::Free | Node::Bad => false,
+            Node::Leaf(item) => f(*item),
+            Node::Branch(children) => children.0.iter().any(|child| child.any(f)),
+        }
+    }
+
+    /// drops every value in this node that doesn't outrank the new value.
+    fn drop_outranked(
+        &mut self,
+        outranks: &impl Fn(Index) -> bool,
+        drop_item: &mut impl FnMut(Index),
+    ) {
+        match self {
+            Node::Free | Node::Bad => {}
+            Node::Leaf(item) => {
+                if !outranks(*item) {
+                    self.drop_with(drop_item);
+                }
+            }
+            Node::Branch(children) => {
+                for child in children.0.iter_mut() {
+                    child.drop_outranked(outranks, drop_item);
+                }
+            }
+        }
+    }
+
+    /// checks whether anything was set above, at, or below a path.
+    pub fn overlaps(&self, mut path: impl Position) -> bool {
+        match self {
+            Node::Free => false,
+            Node::Bad | Node::Leaf(_) => true,
             Node::Branch(children) => match path.pop_front() {
-                Some(subtile) => children[subtile].set(path, value, drop_item),
-                None => reject(self),
+                Some(subtile) => children[subtile].overlaps(path),
+                None => true,
             },
         }
     }
@@ -100,27 +171,34 @@ impl Debug for Node {
 }
 
 /// builds a quadtree from braces, values, and dots
-/// ```
-/// let tree = tree! ({
-///     { .  () () .  }
-///     { () () .  () }
-///     { }
+/// ```ignore
+/// let tree = tree!({
+///     { .  0  1  . }
+///     { 2  3  .  4 }
+///     X
 ///     .
 /// });
-/// println!("{tree:?}");
+/// assert_eq!(format!("{tree:?}"), "{ { . 0 1 . } { 2 3 . 4 } X . }");
 /// ```
+#[cfg(test)]
 macro_rules! tree {
     (.) => {
-        Node::Free
+        $crate::sim::logic::tree::Node::Free
     };
     (X) => {
-        Node::Bad
+        $crate::sim::logic::tree::Node::Bad
     };
     ({ $a:tt $b:tt $c:tt $d:tt }) => {
-        Node::Branch(Box::new(Quad([tree!($a), tree!($b), tree!($c), tree!($d)])))
+        $crate::sim::logic::tree::Node::Branch(Box::new($crate::sim::logic::tile::Quad([
+            tree!($a),
+            tree!($b),
+            tree!($c),
+            tree!($d),
+        ])))
     };
     ($t:expr) => {
-        Node::Leaf($t)
+        $crate::sim::logic::tree::Node::Leaf($t)
     };
 }
+#[cfg(test)]
 pub(crate) use tree;
@@ -1,80 +1,194 @@
 #[cfg(test)]
 mod tests;
 
-use std::collections::{BTreeSet, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashSet},
+};
 
 use crate::sim::logic::{
+    actions::Priority,
     fractal::Fractal,
     orientation::Transform,
-    path::TilePos,
+    path::{Position, TilePos},
     tile::{Quad, Tile},
 };
 
 use super::*;
 
+type Move<P> = (P, (P, Transform));
+
+/// decides what happens when moves get in each other's way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CollisionPolicy {
+    /// conflicting moves cancel each other out,
+    /// and moves into occupied tiles are cancelled.
+    #[default]
+    Cancel,
+
+    /// out of every conflicting move, the one that was added first wins.
+    FirstWins,
+
+    /// out of every conflicting move, the one moving the largest tile wins.
+    /// moves of the same size cancel each other out.
+    LargerWins,
+
+    /// moving into an occupied tile pushes it along by the same offset,
+    /// possibly pushing more tiles along the way.
+    /// a chain of pushes fails if it runs into a wall or a partial tile.
+    Push,
+}
+
+impl CollisionPolicy {
+    /// how much a move should be preferred over the moves it conflicts with.
+    /// priority always comes first, and the policy only breaks ties.
+    ///
+    /// conflicting moves of equal rank cancel each other out.
+    fn rank<P: Position>(
+        self,
+        idx: usize,
+        ((src, _dst), priority): (Move<P>, Priority),
+    ) -> (Priority, i64) {
+        let tiebreak = match self {
+            Self::Cancel | Self::Push => 0,
+            Self::FirstWins => -(idx as i64),
+            Self::LargerWins => -(src.depth() as i64),
+        };
+        (priority, tiebreak)
+    }
+}
+
 /// temporary struct to represent a bunch of moves
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct RawMoveList {
-    moves: Vec<(TilePos, (TilePos, Transform))>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawMoveList<P = TilePos> {
+    moves: Vec<(Move<P>, Priority)>,
+}
+
+impl<P> Default for RawMoveList<P> {
+    fn default() -> Self {
+        Self { moves: vec![] }
+    }
 }
 
 // TODO: figure out how to make the coupling with the fractal quadtree clearer,
 // because Fractal <- RawMoveList <- Node<LeafItem> and the dependence is clear
 
-impl RawMoveList {
-    pub fn add(&mut self, from: TilePos, to: TilePos, transform: Transform) {
-        self.moves.push((from, (to, transform)));
+impl<P: Position> RawMoveList<P> {
+    pub fn add(&mut self, from: P, to: P, transform: Transform, priority: Priority) {
+        self.moves.push(((from, (to, transform)), priority));
     }
 
     /// applies all the moves, resolving conflicts on the way,
     /// and returning only the moves that were executed.
-    pub fn apply(mut self, tree: &mut Fractal) -> CleanMoveList {
+    ///
+    /// every source is vacated before any destination is checked,
+    /// so swaps and cycles of any length are allowed,
+    /// even when the tiles involved are at different depths.
+    /// a move only fails if its destination is blocked by a tile that stays put,
+    /// in which case every move that depended on it fails as well.
+    pub fn apply(self, tree: &mut Fractal<P>, policy: CollisionPolicy) -> CleanMoveList<P> {
+        self.apply_bounded(tree, policy).0
+    }
+
+    /// like `apply`, but also returns whether a push ran off the edge of the fractal.
+    pub(crate) fn apply_bounded(
+        mut self,
+        tree: &mut Fractal<P>,
+        policy: CollisionPolicy,
+    ) -> (CleanMoveList<P>, bool) {
         self.clean_sources(tree);
-        self.clean_forks();
-        self.clean_merges();
+        let escaped = policy == CollisionPolicy::Push && self.add_pushes(tree);
+        let attempted = self.moves.len();
+        self.clean_overlaps(policy, |(src, _dst)| src);
+        self.clean_overlaps(policy, |(_src, (dst, _tf))| dst);
         self.clean_dead_ends(tree);
-        CleanMoveList { inner: self }
+        let cancelled = attempted - self.moves.len();
+        (
+            CleanMoveList {
+                inner: self,
+                cancelled,
+            },
+            escaped,
+        )
     }
 
-    fn clean_sources(&mut self, tree: &Fractal) {
+    /// removes the moves at the specified indices, preserving the order of the rest.
+    fn remove_all(&mut self, holes: &BTreeSet<usize>) {
+        let mut i = 0;
+        self.moves.retain(|_| {
+            i += 1;
+            !holes.contains(&(i - 1))
+        });
+    }
+
+    fn clean_sources(&mut self, tree: &Fractal<P>) {
         let mut set = HashSet::new();
+        self.moves.retain(|&item @ ((src, _dst), _priority)| {
+            set.insert(item) && tree.get_info(tree.get(src).id).fill.is_full()
+        });
+    }
+
+    /// pushes every tile that a move would run into,
+    /// and every tile that those tiles would run into, and so on.
+    ///
+    /// returns whether any tile would have been pushed off the edge.
+    fn add_pushes(&mut self, tree: &Fractal<P>) -> bool {
+        let mut escaped = false;
+        let mut sources = self
+            .moves
+            .iter()
+            .map(|&((src, _dst), _priority)| src)
+            .collect::<HashSet<P>>();
+
         let mut i = 0;
-        while let Some(item @ (src, _dst)) = self.moves.get(i).copied() {
-            if set.insert(item) && tree.get_info(tree.get(src).id).fill.is_full() {
-                i += 1;
-            } else {
-                self.moves.swap_remove(i);
+        while let Some(((src, (dst, _tf)), priority)) = self.moves.get(i).copied() {
+            i += 1;
+            if sources.contains(&dst) {
+                continue;
+            }
+            let Some(step) = src.step_to(dst) else {
+                continue;
+            };
+            if !tree.get_info(tree.get(dst).id).fill.is_full() {
+                continue;
             }
+            let Some(next) = dst + step else {
+                escaped = true;
+                continue;
+            };
+            sources.insert(dst);
+            self.moves.push(((dst, (next, Transform::KU)), priority));
         }
+        escaped
     }
 
-    fn clean_forks(&mut self) {
-        let mut tree = Node::default();
-        let mut holes = BTreeSet::new();
-        for (i, (src, _dst)) in self.moves.iter().copied().enumerate() {
-            tree.set(src, i, &mut |idx| {
-                holes.insert(idx);
-            });
-        }
-        for idx in holes.into_iter().rev() {
-            self.moves.swap_remove(idx);
-        }
-    }
+    /// removes moves whose keys overlap, according to a collision policy.
+    ///
+    /// a move is removed if it overlaps a move of higher or equal rank.
+    /// moves that cancel each other out still block moves of lower rank.
+    fn clean_overlaps(&mut self, policy: CollisionPolicy, key: fn(Move<P>) -> P) {
+        let ranks = self
+            .moves
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(i, mv)| policy.rank(i, mv))
+            .collect::<Vec<_>>();
+        let mut order = (0..self.moves.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&i| Reverse(ranks[i]));
 
-    fn clean_merges(&mut self) {
         let mut tree = Node::default();
         let mut holes = BTreeSet::new();
-        for (i, (_src, (dst, _tf))) in self.moves.iter().copied().enumerate() {
-            tree.set(dst, i, &mut |idx| {
+        for i in order {
+            let outranks = |j: Index| ranks[j] > ranks[i];
+            tree.set_ranked(key(self.moves[i].0), i, &outranks, &mut |idx| {
                 holes.insert(idx);
             });
         }
-        for idx in holes.into_iter().rev() {
-            self.moves.swap_remove(idx);
-        }
+        self.remove_all(&holes);
     }
 
-    fn clean_dead_ends(&mut self, main_fractal: &mut Fractal) {
+    fn clean_dead_ends(&mut self, main_fractal: &mut Fractal<P>) {
         /*
         "Valid until proven otherwise."
 
@@ -149,7 +263,7 @@ impl RawMoveList {
 
         // take out all the source tiles
         let mut old_tiles = vec![];
-        for (i, (src, (dst, _tf))) in self.moves.iter().copied().enumerate() {
+        for (i, ((src, (dst, _tf)), _priority)) in self.moves.iter().copied().enumerate() {
             let old_tile = main_fractal.set(src, Tile::SPACE);
             assert_ne!(old_tile, Tile::SPACE);
             assert!(main_fractal.get_info(old_tile.id).fill.is_full());
@@ -159,7 +273,7 @@ impl RawMoveList {
 
         // mark dead ends as dead
         let mut dead = vec![];
-        for (i, (_src, (dst, _tf))) in self.moves.iter().copied().enumerate() {
+        for (i, ((_src, (dst, _tf)), _priority)) in self.moves.iter().copied().enumerate() {
             if main_fractal.get(dst) != Tile::SPACE {
                 dead.push(i);
             }
@@ -168,7 +282,7 @@ impl RawMoveList {
         // invalidate dead ends and mark their dependents
         // preserve ordering
         while let Some(i) = dead.pop() {
-            let (src, _dst_tf) = self.moves[i];
+            let ((src, _dst_tf), _priority) = self.moves[i];
             if old_tiles[i] == Tile::SPACE {
                 continue;
             }
@@ -179,7 +293,7 @@ impl RawMoveList {
 
         // execute all the moves, take out failed ones, retain working ones
         let mut out = vec![];
-        for (mv @ (_src, (dst, tf)), tile) in self.moves.drain(..).zip(old_tiles) {
+        for (mv @ ((_src, (dst, tf)), _priority), tile) in self.moves.drain(..).zip(old_tiles) {
             if tile == Tile::SPACE {
                 continue;
             }
@@ -189,7 +303,7 @@ impl RawMoveList {
         self.moves = out;
 
         impl<T> Tree<T> {
-            fn set(&mut self, mut pos: TilePos, val: T) {
+            fn set(&mut self, mut pos: impl Position, val: T) {
                 assert!(
                     !matches!(self, Tree::Leaf(_)),
                     "should be no collisions at this point"
@@ -219,7 +333,7 @@ impl RawMoveList {
         // DstTree
         // dsttree will be indexed by src
         impl Tree<Index> {
-            fn invalidate(&mut self, mut pos: TilePos, drop_item: &mut impl FnMut(Index)) {
+            fn invalidate(&mut self, mut pos: impl Position, drop_item: &mut impl FnMut(Index)) {
                 match self {
                     Tree::Free => {}
                     Tree::Leaf(_) => self.drop_with(drop_item),
@@ -262,9 +376,36 @@ impl RawMoveList {
     }
 }
 
+/// the moves that were executed, after every conflict was resolved.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct CleanMoveList {
-    inner: RawMoveList,
+pub struct CleanMoveList<P = TilePos> {
+    inner: RawMoveList<P>,
+    cancelled: usize,
+}
+
+impl<P: Position> CleanMoveList<P> {
+    pub fn len(&self) -> usize {
+        self.inner.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.moves.is_empty()
+    }
+
+    /// how many moves were cancelled by a conflict or a blocked destination.
+    /// moves of empty tiles don't count, since they never had anything to move.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+
+    /// every move as its source, its destination,
+    /// and how the tile was rotated or reflected on the way.
+    pub fn iter(&self) -> impl Iterator<Item = (P, P, Transform)> + '_ {
+        self.inner
+            .moves
+            .iter()
+            .map(|&((src, (dst, transform)), _priority)| (src, dst, transform))
+    }
 }
 
 // TODO: double check all pub visibilities
@@ -1,5 +1,11 @@
 use super::*;
-use crate::sim::logic::tile::SubTile::{self, *};
+use crate::sim::logic::{
+    path::SquarePos,
+    planet::Planet,
+    tile::SubTile::{self, *},
+};
+use glam::IVec2;
+use std::collections::HashMap;
 
 #[test]
 fn test_macro() {
@@ -168,3 +174,533 @@ fn test_node_set_hit_branch() {
         ),
     ])
 }
+
+#[test]
+fn test_node_set_ranked() {
+    let mut node = Node::default();
+    let mut dropped = vec![];
+    let mut drop_item = |idx| dropped.push(idx);
+    node.set(path(&[C, U]), 0, &mut drop_item);
+    node.set(path(&[C, R]), 1, &mut drop_item);
+
+    // 0 outranks 2, but 1 doesn't
+    node.set_ranked(path(&[C]), 2, &|idx| idx == 0, &mut drop_item);
+    assert_eq!(node, tree!({ { . 0 X . } . . . }));
+
+    // Bad nodes always win
+    node.set_ranked(path(&[C, R, L]), 3, &|_| false, &mut drop_item);
+    assert_eq!(node, tree!({ { . 0 X . } . . . }));
+    assert_eq!(dropped, [2, 1, 3]);
+}
+
+fn path(subtiles: &[SubTile]) -> TilePos {
+    TilePos::from_inward_path(subtiles.iter().copied())
+}
+
+/// finds an xyyy fragment by name, as an upright tile
+fn tile(name: &str) -> Tile {
+    Planet::new_xyyy().fragments().tile(name).unwrap()
+}
+
+fn xyyy_fractal() -> Fractal {
+    Planet::new_xyyy().fragments().build_fractal().unwrap()
+}
+
+/// builds an empty xyyy fractal, then fills it with the specified tiles
+fn fractal_with(tiles: &[(&[SubTile], Tile)]) -> Fractal {
+    let mut fractal = xyyy_fractal();
+    fractal.set(TilePos::UNIT, Tile::SPACE);
+    for (subtiles, tile) in tiles.iter().copied() {
+        fractal.set(path(subtiles), tile);
+    }
+    fractal
+}
+
+fn apply_moves(
+    fractal: &mut Fractal,
+    policy: CollisionPolicy,
+    moves: &[(&[SubTile], &[SubTile], Transform)],
+) -> usize {
+    let moves = moves
+        .iter()
+        .map(|&(src, dst, tf)| (src, dst, tf, 0))
+        .collect::<Vec<_>>();
+    apply_prioritized(fractal, policy, &moves)
+}
+
+fn apply_prioritized(
+    fractal: &mut Fractal,
+    policy: CollisionPolicy,
+    moves: &[(&[SubTile], &[SubTile], Transform, Priority)],
+) -> usize {
+    let mut list = RawMoveList::default();
+    for (src, dst, tf, priority) in moves.iter().copied() {
+        list.add(path(src), path(dst), tf, priority);
+    }
+    list.apply(fractal, policy).inner.moves.len()
+}
+
+#[test]
+fn test_swap() {
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[L], tile("Flip-Flop"))]);
+    let executed = apply_moves(
+        &mut fractal,
+        CollisionPolicy::Cancel,
+        &[(&[U], &[L], Transform::KU), (&[L], &[U], Transform::KU)],
+    );
+    assert_eq!(executed, 2);
+    assert_eq!(fractal.get(path(&[U])), tile("Flip-Flop"));
+    assert_eq!(fractal.get(path(&[L])), tile("X"));
+}
+
+#[test]
+fn test_cycle() {
+    let cycle: [&[SubTile]; 5] = [&[U, U], &[U, R], &[R, C], &[L, L], &[C, U]];
+    let tiles = [
+        tile("X"),
+        tile("Y"),
+        tile("Flip-Flop"),
+        tile("Spinner"),
+        tile("Rotor"),
+    ];
+    let mut fractal = fractal_with(&[
+        (cycle[0], tiles[0]),
+        (cycle[1], tiles[1]),
+        (cycle[2], tiles[2]),
+        (cycle[3], tiles[3]),
+        (cycle[4], tiles[4]),
+    ]);
+
+    let moves = (0..cycle.len())
+        .map(|i| (cycle[i], cycle[(i + 1) % cycle.len()], Transform::KR))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::Cancel, &moves),
+        cycle.len()
+    );
+
+    for i in 0..cycle.len() {
+        let dst = cycle[(i + 1) % cycle.len()];
+        assert_eq!(fractal.get(path(dst)), tiles[i] + Transform::KR);
+    }
+}
+
+#[test]
+fn test_blocked_chain() {
+    // a chain of moves ending in a tile that doesn't move out of the way
+    let chain: [&[SubTile]; 4] = [&[U, U], &[U, R], &[R, C], &[L, L]];
+    let mut fractal = fractal_with(&[
+        (chain[0], tile("X")),
+        (chain[1], tile("Y")),
+        (chain[2], tile("Flip-Flop")),
+        (chain[3], tile("Spinner")),
+    ]);
+    let before = fractal.root;
+
+    let moves = chain
+        .windows(2)
+        .map(|pair| (pair[0], pair[1], Transform::KU))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::Cancel, &moves),
+        0
+    );
+    assert_eq!(fractal.root, before);
+}
+
+#[test]
+fn test_mixed_depth_cycle() {
+    // one big tile moves into a slot vacated by 4 small tiles,
+    // which move into the slot vacated by the big tile.
+    let mut fractal = fractal_with(&[
+        (&[U], tile("Rotor")),
+        (&[L, C], tile("X")),
+        (&[L, U], tile("Y")),
+        (&[L, R], tile("Flip-Flop")),
+        (&[L, L], tile("Spinner")),
+    ]);
+    let small = [tile("X"), tile("Y"), tile("Flip-Flop"), tile("Spinner")];
+
+    let mut moves: Vec<(&[SubTile], &[SubTile], Transform)> = vec![(&[U], &[L], Transform::KU)];
+    let srcs: [&[SubTile]; 4] = [&[L, C], &[L, U], &[L, R], &[L, L]];
+    let dsts: [&[SubTile]; 4] = [&[U, C], &[U, U], &[U, R], &[U, L]];
+    for (src, dst) in srcs.into_iter().zip(dsts) {
+        moves.push((src, dst, Transform::KU));
+    }
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::Cancel, &moves),
+        5
+    );
+
+    assert_eq!(fractal.get(path(&[L])), tile("Rotor"));
+    for (dst, tile) in dsts.into_iter().zip(small) {
+        assert_eq!(fractal.get(path(dst)), tile);
+    }
+}
+
+#[test]
+fn test_mixed_depth_cycle_blocked() {
+    // same as above, but one of the small tiles is blocked,
+    // which blocks the big tile, which blocks the rest of the small tiles.
+    let mut fractal = fractal_with(&[
+        (&[U], tile("Rotor")),
+        (&[R], tile("X")),
+        (&[L, C], tile("X")),
+        (&[L, U], tile("Y")),
+        (&[L, R], tile("Flip-Flop")),
+        (&[L, L], tile("Spinner")),
+    ]);
+    let before = fractal.root;
+
+    let moves: [(&[SubTile], &[SubTile], Transform); 5] = [
+        (&[U], &[L], Transform::KU),
+        (&[L, C], &[R], Transform::KU),
+        (&[L, U], &[U, U], Transform::KU),
+        (&[L, R], &[U, R], Transform::KU),
+        (&[L, L], &[U, L], Transform::KU),
+    ];
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::Cancel, &moves),
+        0
+    );
+    assert_eq!(fractal.root, before);
+}
+
+#[test]
+fn test_cancelled() {
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[L], tile("Flip-Flop"))]);
+    let mut list = RawMoveList::default();
+    list.add(path(&[U]), path(&[R]), Transform::KU, 0);
+    list.add(path(&[L]), path(&[R]), Transform::KU, 0);
+    // moving space isn't a conflict, there's just nothing to move
+    list.add(path(&[C]), path(&[U]), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Cancel);
+    assert_eq!((executed.len(), executed.cancelled()), (0, 2));
+
+    let mut list = RawMoveList::default();
+    list.add(path(&[U]), path(&[L]), Transform::KU, 0);
+    list.add(path(&[L]), path(&[U]), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Cancel);
+    assert_eq!((executed.len(), executed.cancelled()), (2, 0));
+}
+
+#[test]
+fn test_policy_cancel() {
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[R], tile("Flip-Flop"))]);
+    let before = fractal.root;
+    let moves: [(&[SubTile], &[SubTile], Transform); 2] =
+        [(&[U], &[L], Transform::KU), (&[R], &[L], Transform::KU)];
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::Cancel, &moves),
+        0
+    );
+    assert_eq!(fractal.root, before);
+}
+
+#[test]
+fn test_policy_first_wins() {
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[R], tile("Flip-Flop"))]);
+    let moves: [(&[SubTile], &[SubTile], Transform); 3] = [
+        (&[R], &[L], Transform::KU),
+        (&[U], &[L], Transform::KU),
+        (&[U], &[C], Transform::KU),
+    ];
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::FirstWins, &moves),
+        1
+    );
+    assert_eq!(fractal.get(path(&[L])), tile("Flip-Flop"));
+    assert_eq!(fractal.get(path(&[U])), tile("X"));
+    assert_eq!(fractal.get(path(&[R])), Tile::SPACE);
+}
+
+#[test]
+fn test_policy_larger_wins() {
+    let mut fractal = fractal_with(&[
+        (&[U], tile("X")),
+        (&[R, C], tile("Flip-Flop")),
+        (&[R, U], tile("Spinner")),
+    ]);
+    let moves: [(&[SubTile], &[SubTile], Transform); 3] = [
+        (&[R, C], &[L, C], Transform::KU),
+        (&[U], &[L], Transform::KU),
+        (&[R, U], &[L, U], Transform::KU),
+    ];
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::LargerWins, &moves),
+        1
+    );
+    assert_eq!(fractal.get(path(&[L])), tile("X"));
+    assert_eq!(fractal.get(path(&[R, C])), tile("Flip-Flop"));
+    assert_eq!(fractal.get(path(&[R, U])), tile("Spinner"));
+
+    // equal sizes still cancel each other out
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[R], tile("Flip-Flop"))]);
+    let before = fractal.root;
+    let moves: [(&[SubTile], &[SubTile], Transform); 2] =
+        [(&[U], &[L], Transform::KU), (&[R], &[L], Transform::KU)];
+    assert_eq!(
+        apply_moves(&mut fractal, CollisionPolicy::LargerWins, &moves),
+        0
+    );
+    assert_eq!(fractal.root, before);
+}
+
+#[test]
+fn test_policy_push() {
+    // a row of 3 tiles at depth 2, each pushing the next one to the right
+    let row = |x| TilePos {
+        depth: 2,
+        pos: IVec2 { x, y: 3 },
+        flop: false,
+    };
+    let mut fractal = xyyy_fractal();
+    fractal.set(TilePos::UNIT, Tile::SPACE);
+    fractal.set(row(0), tile("X"));
+    fractal.set(row(1), tile("Flip-Flop"));
+    fractal.set(row(2), tile("Spinner"));
+
+    let mut list = RawMoveList::default();
+    list.add(row(0), row(1), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Push);
+    assert_eq!(executed.inner.moves.len(), 3);
+    assert_eq!(fractal.get(row(0)), Tile::SPACE);
+    assert_eq!(fractal.get(row(1)), tile("X"));
+    assert_eq!(fractal.get(row(2)), tile("Flip-Flop"));
+    assert_eq!(fractal.get(row(3)), tile("Spinner"));
+
+    // the row can't be pushed off the edge
+    let before = fractal.root;
+    let mut list = RawMoveList::default();
+    list.add(row(1), row(2), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Push);
+    assert_eq!(executed.inner.moves.len(), 0);
+    assert_eq!(fractal.root, before);
+}
+
+#[test]
+fn test_priority() {
+    // the higher priority move wins, regardless of order
+    for policy in [CollisionPolicy::Cancel, CollisionPolicy::FirstWins] {
+        let mut fractal = fractal_with(&[(&[U], tile("X")), (&[R], tile("Flip-Flop"))]);
+        let moves: [(&[SubTile], &[SubTile], Transform, Priority); 2] = [
+            (&[U], &[L], Transform::KU, 0),
+            (&[R], &[L], Transform::KU, 1),
+        ];
+        assert_eq!(apply_prioritized(&mut fractal, policy, &moves), 1);
+        assert_eq!(fractal.get(path(&[L])), tile("Flip-Flop"));
+        assert_eq!(fractal.get(path(&[U])), tile("X"));
+    }
+
+    // priority beats size
+    let mut fractal = fractal_with(&[(&[U], tile("X")), (&[R, C], tile("Flip-Flop"))]);
+    let moves: [(&[SubTile], &[SubTile], Transform, Priority); 2] = [
+        (&[U], &[L], Transform::KU, 0),
+        (&[R, C], &[L, C], Transform::KU, 1),
+    ];
+    assert_eq!(
+        apply_prioritized(&mut fractal, CollisionPolicy::LargerWins, &moves),
+        1
+    );
+    assert_eq!(fractal.get(path(&[L, C])), tile("Flip-Flop"));
+    assert_eq!(fractal.get(path(&[U])), tile("X"));
+}
+
+#[test]
+fn test_priority_ties() {
+    // ties between the highest priorities cancel out,
+    // which leaves the way open for nobody, not even lower priorities
+    let mut fractal = fractal_with(&[
+        (&[U], tile("X")),
+        (&[R], tile("Flip-Flop")),
+        (&[C], tile("Spinner")),
+    ]);
+    let before = fractal.root;
+    let moves: [(&[SubTile], &[SubTile], Transform, Priority); 3] = [
+        (&[U], &[L], Transform::KU, 2),
+        (&[R], &[L], Transform::KU, 2),
+        (&[C], &[L], Transform::KU, 1),
+    ];
+    assert_eq!(
+        apply_prioritized(&mut fractal, CollisionPolicy::Cancel, &moves),
+        0
+    );
+    assert_eq!(fractal.root, before);
+}
+
+/// every position at a certain depth
+fn flat_positions(depth: u8) -> Vec<TilePos> {
+    let mut out = vec![TilePos::UNIT];
+    for _ in 0..depth {
+        out = out
+            .into_iter()
+            .flat_map(|pos| {
+                SubTile::QUAD.into_iter().map(move |subtile| {
+                    let mut pos = pos;
+                    pos.push_back(subtile);
+                    pos
+                })
+            })
+            .collect();
+    }
+    out
+}
+
+type LinearMove = (usize, usize, Transform);
+
+/// the linear collision resolver from `examples/collide.rs`, without the fractals:
+/// - moves from holes are dropped, and duplicate moves are merged
+/// - forks (moves sharing a source) are dropped
+/// - merges (moves sharing a destination) are dropped
+/// - dead ends (moves into a slot that isn't vacated) are dropped,
+///   along with every move that depended on them
+///
+/// returns the moves that were executed.
+fn linear_apply(items: &mut [Tile], moves: &[LinearMove]) -> BTreeSet<LinearMove> {
+    let moves = moves
+        .iter()
+        .copied()
+        .filter(|&(src, _, _)| items[src] != Tile::SPACE)
+        .collect::<BTreeSet<LinearMove>>();
+
+    let without_overlaps = |moves: BTreeSet<LinearMove>, key: fn(&LinearMove) -> usize| {
+        let mut counts = vec![0; items.len()];
+        for mv in &moves {
+            counts[key(mv)] += 1;
+        }
+        moves
+            .into_iter()
+            .filter(|mv| counts[key(mv)] == 1)
+            .collect::<BTreeSet<LinearMove>>()
+    };
+    let moves = without_overlaps(moves, |&(src, _, _)| src);
+    let mut moves = without_overlaps(moves, |&(_, dst, _)| dst);
+
+    loop {
+        let vacated = moves
+            .iter()
+            .map(|&(src, _, _)| src)
+            .collect::<HashSet<usize>>();
+        let dead = moves
+            .iter()
+            .copied()
+            .filter(|&(_, dst, _)| items[dst] != Tile::SPACE && !vacated.contains(&dst))
+            .collect::<Vec<_>>();
+        if dead.is_empty() {
+            break;
+        }
+        for mv in dead {
+            moves.remove(&mv);
+        }
+    }
+
+    let taken = moves
+        .iter()
+        .map(|&(src, _, _)| std::mem::replace(&mut items[src], Tile::SPACE))
+        .collect::<Vec<Tile>>();
+    for (&(_, dst, tf), tile) in moves.iter().zip(taken) {
+        items[dst] = tile + tf;
+    }
+    moves
+}
+
+/// compares RawMoveList::apply against linear_apply on flat fractals,
+/// where every move happens at the same depth
+#[test]
+fn fuzz_flat() {
+    use rand::prelude::*;
+
+    let mut rng = StdRng::seed_from_u64(0xF4AC7041);
+    let depth = 2;
+    let positions = flat_positions(depth);
+    let indices = positions
+        .iter()
+        .enumerate()
+        .map(|(i, pos)| (*pos, i))
+        .collect::<HashMap<TilePos, usize>>();
+
+    for _ in 0..1 << 10 {
+        let mut fractal = xyyy_fractal();
+        let leaf_count = fractal.library.len();
+        fractal.set(TilePos::UNIT, Tile::SPACE);
+
+        let mut items = positions
+            .iter()
+            .map(|_| {
+                if rng.gen() {
+                    return Tile::SPACE;
+                }
+                let id = rng.gen_range(1..leaf_count);
+                let orient = fractal.library[id].symmetries.into();
+                Tile { id, orient } + *Transform::TRANSFORMS.choose(&mut rng).unwrap()
+            })
+            .collect::<Vec<Tile>>();
+        for (pos, tile) in positions.iter().zip(&items) {
+            fractal.set(*pos, *tile);
+        }
+
+        // few moves means longer chains, many moves means more cycles and collisions
+        let move_count = rng.gen_range(1..positions.len() * 2);
+        let moves = (0..move_count)
+            .map(|_| {
+                (
+                    rng.gen_range(0..positions.len()),
+                    rng.gen_range(0..positions.len()),
+                    *Transform::TRANSFORMS.choose(&mut rng).unwrap(),
+                )
+            })
+            .collect::<Vec<LinearMove>>();
+
+        let mut list = RawMoveList::default();
+        for (src, dst, tf) in moves.iter().copied() {
+            list.add(positions[src], positions[dst], tf, 0);
+        }
+        let executed = list
+            .apply(&mut fractal, CollisionPolicy::Cancel)
+            .inner
+            .moves
+            .into_iter()
+            .map(|((src, (dst, tf)), _priority)| (indices[&src], indices[&dst], tf))
+            .collect::<BTreeSet<LinearMove>>();
+
+        let expected = linear_apply(&mut items, &moves);
+        assert_eq!(executed, expected, "moves: {moves:?}");
+        for (pos, item) in positions.iter().zip(&items) {
+            assert_eq!(fractal.get(*pos), *item, "moves: {moves:?}");
+        }
+    }
+}
+
+/// collisions don't care about the shape of the tiles
+#[test]
+fn test_square_push() {
+    let row = |x| SquarePos {
+        depth: 2,
+        pos: IVec2 { x, y: 1 },
+    };
+    let mut fractal = Fractal::<SquarePos>::new(&[Quad::ONE]).unwrap();
+    fractal.set(SquarePos::UNIT, Tile::SPACE);
+    fractal.set(row(0), Tile::ONE);
+    fractal.set(row(1), Tile::ONE);
+
+    let mut list = RawMoveList::default();
+    list.add(row(0), row(1), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Push);
+    assert_eq!(executed.inner.moves.len(), 2);
+    assert_eq!(fractal.get(row(0)), Tile::SPACE);
+    assert_eq!(fractal.get(row(1)), Tile::ONE);
+    assert_eq!(fractal.get(row(2)), Tile::ONE);
+
+    let mut list = RawMoveList::default();
+    list.add(row(1), row(2), Transform::KU, 0);
+    list.apply(&mut fractal, CollisionPolicy::Push);
+    assert_eq!(fractal.get(row(3)), Tile::ONE);
+
+    // the row can't be pushed off the edge
+    let before = fractal.root;
+    let mut list = RawMoveList::default();
+    list.add(row(2), row(3), Transform::KU, 0);
+    let executed = list.apply(&mut fractal, CollisionPolicy::Push);
+    assert_eq!(executed.inner.moves.len(), 0);
+    assert_eq!(fractal.root, before);
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+#[test]
+fn test_tree_macro() {
+    let tree = tree!({
+        { .  0  1  . }
+        { 2  3  .  4 }
+        X
+        .
+    });
+    assert_eq!(format!("{tree:?}"), "{ { . 0 1 . } { 2 3 . 4 } X . }");
+}
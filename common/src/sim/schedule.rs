@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests;
+
+use std::{fmt::Display, time::Duration};
+
+/// decides when to tick, so that ticks happen at a fixed rate
+/// no matter how often the game loop runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickScheduler {
+    tick_length: Duration,
+    /// time that has passed, but hasn't been spent on ticks yet
+    backlog: Duration,
+    paused: bool,
+}
+
+impl TickScheduler {
+    /// the most ticks a single call to `advance` will catch up on.
+    /// anything past that is dropped, so a slow frame doesn't snowball into slower ones.
+    pub const MAX_CATCH_UP: u32 = 10;
+
+    pub fn new(ticks_per_sec: f32) -> Result<Self, RateError> {
+        Ok(Self {
+            tick_length: tick_length(ticks_per_sec)?,
+            backlog: Duration::ZERO,
+            paused: false,
+        })
+    }
+
+    pub fn tick_length(&self) -> Duration {
+        self.tick_length
+    }
+
+    pub fn ticks_per_sec(&self) -> f32 {
+        1.0 / self.tick_length.as_secs_f32()
+    }
+
+    /// changes the tick rate, keeping the old one if the new one is invalid.
+    pub fn set_ticks_per_sec(&mut self, ticks_per_sec: f32) -> Result<(), RateError> {
+        self.tick_length = tick_length(ticks_per_sec)?;
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// stops ticking, and forgets any time that hasn't been spent on ticks yet.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.backlog = Duration::ZERO;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// lets some time pass, and returns how many ticks should happen because of it.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        if self.paused {
+            return 0;
+        }
+        self.backlog += elapsed;
+        let ticks = self.backlog.as_nanos() / self.tick_length.as_nanos();
+        if ticks > Self::MAX_CATCH_UP as u128 {
+            self.backlog = Duration::ZERO;
+            return Self::MAX_CATCH_UP;
+        }
+        let ticks = ticks as u32;
+        self.backlog -= self.tick_length * ticks;
+        ticks
+    }
+
+    /// how long until the next tick is due.
+    /// returns `Duration::MAX` while paused.
+    pub fn until_next_tick(&self) -> Duration {
+        if self.paused {
+            return Duration::MAX;
+        }
+        self.tick_length.saturating_sub(self.backlog)
+    }
+}
+
+/// how long each tick lasts at a rate.
+/// rates too fast to measure still take at least 1ns, so `advance` never divides by 0.
+fn tick_length(ticks_per_sec: f32) -> Result<Duration, RateError> {
+    if !ticks_per_sec.is_finite() || ticks_per_sec <= 0.0 {
+        return Err(RateError::Invalid(ticks_per_sec));
+    }
+    let length = Duration::try_from_secs_f32(1.0 / ticks_per_sec)
+        .map_err(|_| RateError::TooSlow(ticks_per_sec))?;
+    Ok(length.max(Duration::from_nanos(1)))
+}
+
+/// why a tick rate couldn't be used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateError {
+    /// the rate isn't a positive, finite number
+    Invalid(f32),
+    /// a single tick would last longer than a `Duration` can hold
+    TooSlow(f32),
+}
+
+impl Display for RateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(rate) => write!(f, "{rate} ticks/s is not a positive rate"),
+            Self::TooSlow(rate) => write!(f, "{rate} ticks/s is too slow"),
+        }
+    }
+}
+
+impl std::error::Error for RateError {}
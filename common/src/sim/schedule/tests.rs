@@ -0,0 +1,71 @@
+use super::*;
+
+#[test]
+fn test_fixed_rate() {
+    let mut scheduler = TickScheduler::new(4.0).unwrap();
+    let quarter = Duration::from_millis(250);
+
+    assert_eq!(scheduler.advance(quarter / 2), 0);
+    assert_eq!(scheduler.until_next_tick(), quarter / 2);
+    assert_eq!(scheduler.advance(quarter / 2), 1);
+    assert_eq!(scheduler.advance(quarter * 3 + quarter / 2), 3);
+    assert_eq!(scheduler.advance(quarter / 2), 1);
+    assert_eq!(scheduler.until_next_tick(), quarter);
+}
+
+#[test]
+fn test_pause() {
+    let mut scheduler = TickScheduler::new(4.0).unwrap();
+    let quarter = Duration::from_millis(250);
+
+    assert_eq!(scheduler.advance(quarter / 2), 0);
+    scheduler.toggle_pause();
+    assert!(scheduler.is_paused());
+    assert_eq!(scheduler.advance(quarter * 8), 0);
+    assert_eq!(scheduler.until_next_tick(), Duration::MAX);
+
+    // time before the pause is forgotten
+    scheduler.toggle_pause();
+    assert_eq!(scheduler.advance(quarter / 2), 0);
+    assert_eq!(scheduler.advance(quarter / 2), 1);
+}
+
+#[test]
+fn test_catch_up() {
+    let mut scheduler = TickScheduler::new(4.0).unwrap();
+    assert_eq!(
+        scheduler.advance(Duration::from_secs(60)),
+        TickScheduler::MAX_CATCH_UP
+    );
+    assert_eq!(scheduler.advance(Duration::ZERO), 0);
+}
+
+#[test]
+fn test_invalid_rates() {
+    for rate in [0.0, -0.0, -4.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        // NaN never equals itself, so the rate isn't compared
+        assert!(
+            matches!(TickScheduler::new(rate), Err(RateError::Invalid(_))),
+            "{rate}"
+        );
+    }
+    assert_eq!(TickScheduler::new(1e-30), Err(RateError::TooSlow(1e-30)));
+
+    // a bad rate leaves the old one alone
+    let mut scheduler = TickScheduler::new(4.0).unwrap();
+    assert_eq!(
+        scheduler.set_ticks_per_sec(0.0),
+        Err(RateError::Invalid(0.0))
+    );
+    assert_eq!(scheduler.ticks_per_sec(), 4.0);
+}
+
+#[test]
+fn test_huge_rate() {
+    let mut scheduler = TickScheduler::new(f32::MAX).unwrap();
+    assert_eq!(
+        scheduler.advance(Duration::from_millis(1)),
+        TickScheduler::MAX_CATCH_UP
+    );
+    assert_eq!(scheduler.until_next_tick(), Duration::from_nanos(1));
+}
@@ -0,0 +1,115 @@
+use super::*;
+use crate::sim::logic::{command::Command, path::TilePos};
+
+/// plays back a list of inputs, one frame at a time
+struct Script {
+    frames: Vec<Vec<Input>>,
+    ticks: usize,
+    draws: usize,
+    replay: Option<Replay>,
+}
+
+impl Game for Script {
+    fn input(&mut self, _meta: &FractoryMeta, _planet: &Planet) -> Vec<Input> {
+        if self.frames.is_empty() {
+            vec![Input::Quit]
+        } else {
+            self.frames.remove(0)
+        }
+    }
+
+    fn on_tick(&mut self, _meta: &FractoryMeta, _report: &TickReport) {
+        self.ticks += 1;
+    }
+
+    fn replay(&mut self) -> Option<&mut Replay> {
+        self.replay.as_mut()
+    }
+
+    fn draw(&mut self, _meta: &FractoryMeta, _planet: &Planet) {
+        self.draws += 1;
+    }
+}
+
+#[test]
+fn test_run() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    let pos = TilePos::from_inward_path([]);
+
+    // the same inputs, done by hand
+    let mut expected = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    let planet = planets.get(&expected.planet).unwrap();
+    let biome = planet.biomes().get(&expected.biome).unwrap();
+    expected.fractory.toggle_activation(pos);
+    for _ in 0..3 {
+        expected.tick(planet.fragments(), biome);
+    }
+
+    let mut script = Script {
+        frames: vec![
+            vec![
+                Input::Pause,
+                Input::Command(Command::ToggleActive(pos)),
+                Input::Command(Command::Tick),
+            ],
+            vec![Input::Command(Command::Tick), Input::Command(Command::Tick)],
+        ],
+        ticks: 0,
+        draws: 0,
+        replay: None,
+    };
+    run(&mut script, &planets, &mut meta).unwrap();
+    assert_eq!(script.ticks, 3);
+    assert_eq!(script.draws, 2);
+    assert_eq!(meta.fractory.fractal.root, expected.fractory.fractal.root);
+    assert_eq!(meta.fractory.inventory, expected.fractory.inventory);
+}
+
+#[test]
+fn test_run_missing_biome() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    meta.biome = "Nowhere".into();
+    let mut script = Script {
+        frames: vec![],
+        ticks: 0,
+        draws: 0,
+        replay: None,
+    };
+    let error = run(&mut script, &planets, &mut meta).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert_eq!(script.draws, 0);
+}
+
+#[test]
+fn test_run_replay() {
+    let mut planets = PlanetCache::default();
+    let mut meta = FractoryMeta::new_xyyy(&mut planets);
+    let pos = TilePos::from_inward_path([]);
+    let mut script = Script {
+        frames: vec![vec![
+            Input::Pause,
+            Input::Command(Command::ToggleActive(pos)),
+            Input::Command(Command::Tick),
+            Input::Command(Command::Flip(pos)),
+            Input::Command(Command::Undo),
+            Input::Command(Command::Undo),
+            Input::Command(Command::Undo),
+            Input::Command(Command::Undo),
+        ]],
+        ticks: 0,
+        draws: 0,
+        replay: Some(Replay::new(&meta, &mut CommandHistory::default())),
+    };
+    run(&mut script, &planets, &mut meta).unwrap();
+    let mut replay = script.replay.unwrap();
+    replay.finish(&meta);
+    // the last undo had nothing left to undo
+    assert_eq!(replay.entries.len(), 6);
+
+    let planet = planets.get(&meta.planet).unwrap();
+    let biome = planet.biomes().get(&meta.biome).unwrap();
+    let mut played = FractoryMeta::new_xyyy(&mut PlanetCache::default());
+    replay.play(&mut played, planet.fragments(), biome).unwrap();
+}
@@ -13,6 +13,8 @@ const MAX_INDICES: usize = 5_000;
 struct Batcher {
     filled_meshes: usize,
     meshes: Vec<Mesh>,
+    /// textured meshes, drawn after the plain ones
+    sprites: Vec<Mesh>,
     text_tools: Vec<Box<dyn Fn(&str)>>,
     text: Vec<(TextToolId, String, Mat4)>,
     top_z: f32,
@@ -77,6 +79,45 @@ impl Batcher {
         Self::add_polygon_to_mesh(mesh, &mut self.top_z, points, color)
     }
 
+    fn queue_sprite(
+        &mut self,
+        points: impl ExactSizeIterator<Item = Vec2>,
+        uvs: &[Vec2],
+        texture: Texture2D,
+        color: Color,
+    ) {
+        let len = points.len();
+        // one mesh per texture, unless it fills up
+        let has_room =
+            |m: &Mesh| m.texture == Some(texture) && m.indices.len() + len * 3 < MAX_INDICES;
+        let i = match self.sprites.iter().position(has_room) {
+            Some(i) => i,
+            None => {
+                self.sprites.push(Mesh {
+                    vertices: vec![],
+                    indices: vec![],
+                    texture: Some(texture),
+                });
+                self.sprites.len() - 1
+            }
+        };
+        let mesh = &mut self.sprites[i];
+
+        let off = mesh.vertices.len() as u16;
+        let top_z = self.top_z;
+        mesh.vertices.extend(points.zip(uvs).map(|(p, &uv)| Vertex {
+            position: p.extend(top_z),
+            uv,
+            color,
+        }));
+        for i in 2..len as u16 {
+            mesh.indices.push(off);
+            mesh.indices.push(off + i - 1);
+            mesh.indices.push(off + i);
+        }
+        self.top_z -= 1.0 / MAX_INDICES as f32;
+    }
+
     fn register_text_tool(&mut self, text_tool: Box<dyn Fn(&str)>) -> TextToolId {
         self.text_tools.push(text_tool);
         self.text_tools.len() - 1
@@ -96,6 +137,11 @@ impl Batcher {
                 mesh.indices.clear();
             }
             self.filled_meshes = usize::MAX;
+            for mesh in &mut self.sprites {
+                draw_mesh(mesh);
+                mesh.vertices.clear();
+                mesh.indices.clear();
+            }
             for (tool, text, matrix) in self.text.drain(..) {
                 apply(matrix, || {
                     self.text_tools[tool](&text);
@@ -117,6 +163,7 @@ impl Default for Batcher {
         Self {
             filled_meshes: usize::MAX,
             meshes: vec![],
+            sprites: vec![],
             text_tools: vec![],
             text: vec![],
             top_z: 0.0,
@@ -172,6 +219,20 @@ impl Context {
         self.batcher.queue_polygon(points, color)
     }
 
+    /// like `queue_polygon`, but textured.
+    /// `uvs` has one texture coordinate per point.
+    pub fn queue_sprite(
+        &mut self,
+        points: &[Vec2],
+        uvs: &[Vec2],
+        texture: Texture2D,
+        color: Color,
+    ) {
+        let project = |p: &Vec2| self.matrix.transform_point3(p.extend(0.0)).truncate();
+        let points = points.iter().map(project);
+        self.batcher.queue_sprite(points, uvs, texture, color)
+    }
+
     pub fn register_text_tool(&mut self, text_tool: Box<dyn Fn(&str)>) -> TextToolId {
         self.batcher.register_text_tool(text_tool)
     }
@@ -9,14 +9,19 @@ const DRAW_BRANCHES: bool = false;
 use self::ctx::{Click, Context};
 use ctx::TextToolId;
 use fractory_common::sim::logic::{
-    factory::{ActiveTiles, Fractory, FractoryMeta},
+    command::{Command, CommandHistory},
+    factory::{active::ActiveTiles, Fractory, FractoryMeta, TickReport},
     fractal::{Fractal, SlotInfo, TileFill},
+    fragment::FragmentRegistry,
     orientation::{Orient, Rotation, Transform},
     path::TilePos,
-    planet::{Behavior, Biome, Filter, FragmentData, Planet, PlanetCache},
+    planet::{Behavior, Biome, Filter, Planet, PlanetCache},
+    replay::Replay,
     tile::{SubTile, Tile},
 };
+use fractory_common::{sim::schedule::TickScheduler, TICK_PER_SEC};
 use std::{
+    collections::{HashMap, HashSet},
     f32::consts::TAU,
     ops::{ControlFlow, Mul},
     time::{Duration, Instant},
@@ -44,6 +49,20 @@ const TRIANGLE: [Vec2; 3] = {
     ]
 };
 
+const SQUARE: [Vec2; 4] = [
+    Vec2 { x: -1.0, y: -1.0 },
+    Vec2 { x: 1.0, y: -1.0 },
+    Vec2 { x: 1.0, y: 1.0 },
+    Vec2 { x: -1.0, y: 1.0 },
+];
+
+/// where each corner of `TRIANGLE` is on a sprite, so that sprites are drawn upright
+const TRIANGLE_UVS: [Vec2; 3] = [
+    Vec2 { x: 0.0, y: 1.0 },
+    Vec2 { x: 1.0, y: 1.0 },
+    Vec2 { x: 0.5, y: 0.0 },
+];
+
 struct Resources {
     planets: PlanetCache,
 }
@@ -313,10 +332,10 @@ struct UiElement {
 }
 
 impl UiElement {
-    fn new(res: &mut Resources, font: Font) -> Self {
+    async fn new(res: &mut Resources, font: Font) -> Self {
         Self {
             font,
-            fractory: FractoryElement::new(res),
+            fractory: FractoryElement::new(res).await,
         }
     }
 
@@ -342,51 +361,75 @@ impl UiElement {
 }
 
 struct FractoryCache {
-    fragments: FragmentData,
+    fragments: FragmentRegistry,
     biome: Biome,
+    /// the sprite of every fragment that has one, by id
+    sprites: HashMap<usize, Texture2D>,
+}
+
+impl FractoryCache {
+    /// copies the fragments and biome, and loads their sprites.
+    async fn new(fragments: &FragmentRegistry, biome: &Biome) -> Self {
+        Self {
+            fragments: fragments.to_owned(),
+            biome: biome.to_owned(),
+            sprites: load_sprites(fragments).await,
+        }
+    }
 }
 
 struct FractoryElement {
     fractory_meta: FractoryMeta,
     fractal_view: FractalViewElement,
-    // inventory_view: InventoryViewElement,
+    inventory_view: InventoryViewElement,
+    palette_view: PaletteViewElement,
     cache: FractoryCache,
+    history: CommandHistory,
+    /// every command since the game started, saved with Ctrl+S
+    replay: Replay,
 }
 
 impl FractoryElement {
-    fn new(res: &mut Resources) -> Self {
+    async fn new(res: &mut Resources) -> Self {
         let fractory_meta = FractoryMeta::new_xyyy(&mut res.planets);
         let planet = res.planets.get(&fractory_meta.planet).unwrap();
         let fragments = planet.fragments();
         let biome = planet.biomes().get(&fractory_meta.biome).unwrap();
-        let cache = FractoryCache {
-            fragments: fragments.to_owned(),
-            biome: biome.to_owned(),
-        };
+        let cache = FractoryCache::new(fragments, biome).await;
+        let mut history = CommandHistory::default();
         Self {
+            replay: Replay::new(&fractory_meta, &mut history),
             fractory_meta,
             fractal_view: FractalViewElement::new(),
+            inventory_view: InventoryViewElement::default(),
+            palette_view: PaletteViewElement::new(&cache.biome),
             cache,
+            history,
         }
     }
 
     fn draw(&mut self, ctx: &mut Context, res: &mut Resources, text_tool: TextToolId) {
-        self.draw_inventory(ctx, text_tool);
         self.fractal_view
             .draw(ctx, res, &self.fractory_meta, &self.cache, text_tool);
+        self.draw_inventory(ctx, text_tool);
+        self.palette_view
+            .draw(ctx, &self.fractory_meta.fractory, &self.cache, text_tool);
 
         ctx.apply(shift(0.0, 0.6) * downscale(10.0), |ctx| {
             ctx.queue_text(
                 text_tool,
                 "Esc: quit\n\
                 Tab: toggle shattered view\n\
-                Enter: tick\n\
+                Enter: step one tick | P: play/pause | -/=: tick rate\n\
+                Ctrl+Z: undo | Ctrl+S: save replay\n\
                 Camera:\n\
                 -> WASD: move | Q/E: rotate | F: flip | (Shift+)Space: zoom (out)in\n\
                 -> Click+Drag: move | Scroll: zoom | (Ctrl/Alt)+Scroll: change cursor/background depth\n\
                 Shift+LMB/RMB: Rotate tile (no effect on rotational tiles such as X, Y, Rotor)\n\
                 Ctrl+LMB: Activate tile | Ctrl+RMB: Flip tile (no effect on reflective tiles)\n\
-                Ctrl+Shift+LMB/RMB: Cycle tile\n\
+                Ctrl+Shift+LMB(+Drag): paint brush | Ctrl+Shift+RMB: pick up brush from tile\n\
+                Click palette: choose brush | R/Shift+R: rotate/flip brush\n\
+                Drag from inventory: place tile | Alt+Drag tile off the fractal: store tile\n\
                 *Some edits may change other tiles' rotations. This is normal."
                     .into(),
             );
@@ -394,32 +437,489 @@ impl FractoryElement {
     }
 
     fn draw_inventory(&mut self, ctx: &mut Context, text_tool: TextToolId) {
-        let wrap = (self.fractory_meta.fractory.inventory.len() as f32).sqrt() as usize;
-        for (idx, (tile_id, count)) in self.fractory_meta.fractory.inventory.iter().enumerate() {
-            let x = idx % wrap;
-            let y = idx / wrap;
-        }
+        self.inventory_view
+            .draw(ctx, &self.fractory_meta.fractory, &self.cache, text_tool);
     }
 
     fn input(&mut self, ctx: &mut Context, res: &mut Resources) {
-        self.fractal_view
-            .input(ctx, res, &mut self.fractory_meta.fractory, &self.cache);
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if ctrl && is_key_pressed(KeyCode::S) {
+            self.save_replay();
+            return;
+        }
+
+        self.fractal_view.input(
+            ctx,
+            res,
+            &mut self.fractory_meta,
+            &self.cache,
+            &mut self.history,
+            &mut self.replay,
+            &mut self.inventory_view,
+            &mut self.palette_view,
+        );
+    }
+
+    fn save_replay(&self) {
+        const PATH: &str = "replay.txt";
+        let mut replay = self.replay.clone();
+        replay.finish(&self.fractory_meta);
+        match std::fs::write(PATH, replay.to_string()) {
+            Ok(()) => println!("saved replay to {PATH}"),
+            Err(error) => println!("couldn't save replay: {error}"),
+        }
+    }
+}
+
+/// the panel of stored fragments, which tiles get dragged in and out of.
+#[derive(Default)]
+struct InventoryViewElement {
+    /// what's being dragged, if anything
+    drag: Option<Drag>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Drag {
+    /// a fragment from the inventory, to place on the fractal
+    Fragment(usize),
+    /// a tile on the fractal, to store
+    Tile(TilePos),
+}
+
+impl InventoryViewElement {
+    /// how wide each slot is, in screen space.
+    const SLOT_SIZE: f32 = 0.12;
+
+    /// every fragment with some in the inventory, and how many.
+    fn slots(fractory: &Fractory) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let inventory = fractory.inventory.iter();
+        inventory.filter_map(|(&id, &count)| (count > 0).then_some((id, count)))
+    }
+
+    /// how many slots go in a row, so the panel stays roughly square.
+    fn wrap(len: usize) -> usize {
+        ((len as f32).sqrt().ceil() as usize).max(1)
+    }
+
+    /// where a slot goes, with the slot spanning -1 to 1.
+    fn slot_matrix(idx: usize, wrap: usize) -> Mat4 {
+        let (x, y) = ((idx % wrap) as f32, (idx / wrap) as f32);
+        let center = INVENTORY_POS + Vec2::new(x, y) * Self::SLOT_SIZE;
+        shift(center.x, center.y) * upscale(Self::SLOT_SIZE / 2.0)
+    }
+
+    /// the fragment in the slot under `pos`, if any.
+    fn slot_at(fractory: &Fractory, pos: Vec2) -> Option<usize> {
+        let wrap = Self::wrap(Self::slots(fractory).count());
+        let offset = (pos - INVENTORY_POS) / Self::SLOT_SIZE + 0.5;
+        if offset.x < 0.0 || offset.y < 0.0 || offset.x >= wrap as f32 {
+            return None;
+        }
+        let idx = offset.y as usize * wrap + offset.x as usize;
+        Self::slots(fractory).nth(idx).map(|(id, _)| id)
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        fractory: &Fractory,
+        cache: &FractoryCache,
+        text_tool: TextToolId,
+    ) {
+        let wrap = Self::wrap(Self::slots(fractory).count());
+        for (idx, (id, count)) in Self::slots(fractory).enumerate() {
+            ctx.apply(Self::slot_matrix(idx, wrap), |ctx| {
+                ctx.apply(upscale(0.95), |ctx| ctx.queue_polygon(&SQUARE, DARKGRAY));
+                let icon = shift(0.0, -0.1) * upscale(0.6);
+                ctx.apply(icon, |ctx| draw_fragment(ctx, cache, id, WHITE));
+                ctx.apply(shift(0.55, 0.65) * downscale(3.0), |ctx| {
+                    ctx.queue_text(text_tool, count.to_string())
+                });
+            });
+        }
+
+        // whatever's being dragged follows the mouse
+        let id = match self.drag {
+            Some(Drag::Fragment(id)) => id,
+            Some(Drag::Tile(pos)) => fractory.fractal.get(pos).id,
+            None => {
+                ctx.flush();
+                return;
+            }
+        };
+        if let Some(mouse) = ctx.mouse_pos() {
+            let ghost = shift(mouse.x, mouse.y) * upscale(Self::SLOT_SIZE / 2.0);
+            let color = Color::new(1.0, 1.0, 1.0, 0.7);
+            ctx.apply(ghost, |ctx| draw_fragment(ctx, cache, id, color));
+        }
+        ctx.flush();
+    }
+
+    /// starts and drops drags.
+    /// breaks while dragging, so the camera holds still.
+    fn input(
+        &mut self,
+        ctx: &mut Context,
+        fractal_view: &mut FractalViewElement,
+        fractory: &Fractory,
+    ) -> ControlFlow<Option<Command>> {
+        let alt = is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt);
+        let mouse = ctx.mouse_pos().unwrap_or(Vec2::ZERO);
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let is_full = |&pos: &TilePos| {
+                let fractal = &fractory.fractal;
+                fractal.get_info(fractal.get(pos).id).fill.is_full()
+            };
+            self.drag = match Self::slot_at(fractory, mouse) {
+                Some(id) => Some(Drag::Fragment(id)),
+                None if alt => fractal_view.hover_pos(ctx).filter(is_full).map(Drag::Tile),
+                None => None,
+            };
+        }
+
+        let Some(drag) = self.drag else {
+            return ControlFlow::Continue(());
+        };
+        if !is_mouse_button_released(MouseButton::Left) {
+            return ControlFlow::Break(None);
+        }
+        self.drag = None;
+
+        let hovered = fractal_view.hover_pos(ctx);
+        let command = match drag {
+            Drag::Fragment(id) => hovered.map(|pos| Command::Place(pos, id)),
+            Drag::Tile(pos) => {
+                let over_inventory = Self::slot_at(fractory, mouse).is_some();
+                (hovered.is_none() || over_inventory).then_some(Command::Store(pos))
+            }
+        };
+        ControlFlow::Break(command)
+    }
+}
+
+/// the fragments the biome allows, to paint onto the fractal with.
+struct PaletteViewElement {
+    brush: Brush,
+}
+
+/// what painting puts down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Brush {
+    id: usize,
+    /// applied on top of the fragment's upright orientation
+    transform: Transform,
+}
+
+impl Brush {
+    fn tile(self, fractal: &Fractal) -> Tile {
+        let upright = Orient::from(fractal.library[self.id].symmetries);
+        Tile {
+            id: self.id,
+            orient: upright + self.transform,
+        }
+    }
+}
+
+impl PaletteViewElement {
+    const ENTRY_SIZE: Vec2 = Vec2 { x: 0.36, y: 0.1 };
+
+    /// where the first entry is centered, in screen space.
+    /// the palette hangs just below the controls, so the two never overlap.
+    fn pos() -> Vec2 {
+        Vec2::new(0.8, Control::bottom() + Self::ENTRY_SIZE.y / 2.0)
+    }
+
+    fn new(biome: &Biome) -> Self {
+        // start with something more useful than empty space
+        let filter = biome.fragment_filter();
+        let id = filter.iter().find(|&id| id != Tile::SPACE.id);
+        Self {
+            brush: Brush {
+                id: id.unwrap_or(Tile::SPACE.id),
+                transform: Transform::KU,
+            },
+        }
+    }
+
+    /// where an entry goes, with the entry spanning -1 to 1 vertically.
+    fn entry_matrix(idx: usize) -> Mat4 {
+        let center = Self::pos() + Vec2::new(0.0, idx as f32 * Self::ENTRY_SIZE.y);
+        shift(center.x, center.y) * upscale(Self::ENTRY_SIZE.y / 2.0)
+    }
+
+    /// the fragment in the entry under `pos`, if any.
+    fn entry_at(biome: &Biome, pos: Vec2) -> Option<usize> {
+        let offset = (pos - Self::pos()) / Self::ENTRY_SIZE + 0.5;
+        if !(0.0..1.0).contains(&offset.x) || offset.y < 0.0 {
+            return None;
+        }
+        biome.fragment_filter().iter().nth(offset.y as usize)
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        fractory: &Fractory,
+        cache: &FractoryCache,
+        text_tool: TextToolId,
+    ) {
+        let half_width = Self::ENTRY_SIZE.x / Self::ENTRY_SIZE.y;
+        for (idx, id) in cache.biome.fragment_filter().iter().enumerate() {
+            let selected = id == self.brush.id;
+            ctx.apply(Self::entry_matrix(idx), |ctx| {
+                let background = if selected { GRAY } else { DARKGRAY };
+                ctx.apply(scale(half_width, 0.95), |ctx| {
+                    ctx.queue_polygon(&SQUARE, background)
+                });
+                // the selected fragment shows which way the brush faces
+                let orient = if selected {
+                    let orient = self.brush.tile(&fractory.fractal).orient;
+                    transform_to_mat4(orient.into())
+                } else {
+                    Mat4::IDENTITY
+                };
+                let icon = shift(1.0 - half_width, 0.1) * upscale(0.7) * orient;
+                ctx.apply(icon, |ctx| draw_fragment(ctx, cache, id, WHITE));
+                let name = match cache.fragments.name(id) {
+                    Some(name) => name.to_owned(),
+                    None => id.to_string(),
+                };
+                ctx.apply(shift(0.5, 0.0), |ctx| ctx.queue_text(text_tool, name));
+            });
+        }
+        ctx.flush();
+    }
+
+    /// picks the brush from the palette, and rotates or flips it.
+    /// breaks while the mouse is using the palette.
+    fn input(&mut self, ctx: &mut Context, biome: &Biome) -> ControlFlow<Option<Command>> {
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if is_key_pressed(KeyCode::R) {
+            let change = if shift { Transform::FU } else { Transform::KR };
+            self.brush.transform = self.brush.transform + change;
+        }
+
+        let down = is_mouse_button_down(MouseButton::Left);
+        let released = is_mouse_button_released(MouseButton::Left);
+        let Some(id) = ctx
+            .lmb_pos()
+            .filter(|_| down || released)
+            .and_then(|(pos, _)| Self::entry_at(biome, pos))
+        else {
+            return ControlFlow::Continue(());
+        };
+        self.brush.id = id;
+        ControlFlow::Break(None)
+    }
+}
+
+/// a fragment's own color.
+fn fragment_color(fragments: &FragmentRegistry, id: usize) -> Color {
+    let appearance = fragments.get(id).map(|fragment| &fragment.appearance);
+    let [r, g, b] = appearance.map_or([128; 3], |appearance| appearance.color);
+    Color::from_rgba(r, g, b, 255)
+}
+
+/// draws a fragment's triangle in its color, or with its sprite if it has one.
+/// `tint` is multiplied in.
+fn draw_fragment(ctx: &mut Context, cache: &FractoryCache, id: usize, tint: Color) {
+    let color = fragment_color(&cache.fragments, id);
+    let color = Color::from_vec(color.to_vec() * tint.to_vec());
+    match cache.sprites.get(&id) {
+        Some(&texture) => ctx.queue_sprite(&TRIANGLE, &TRIANGLE_UVS, texture, color),
+        None => ctx.queue_polygon(&TRIANGLE, color),
     }
 }
 
 struct FractalViewElement {
     view_state: ViewState,
     frac_cam: FractalCam,
+    /// the regions that changed during the last tick
+    changed: HashSet<TilePos>,
+    /// the last tick, still playing out
+    animation: Option<TickAnimation>,
+    /// multiplied into every tile's alpha, for fading
+    alpha: f32,
+    scheduler: TickScheduler,
+    /// index into `TICK_RATES`, or one past the end to tick as fast as possible
+    rate: usize,
+    last_frame: Instant,
+}
+
+/// the tick rates the slider goes through, in ticks per second.
+/// past the last one, it ticks as fast as frames allow, without animating.
+const TICK_RATES: [f32; 7] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 60.0];
+
+/// how much of each frame can be spent ticking as fast as possible.
+const HEADLESS_BUDGET: Duration = Duration::from_millis(12);
+
+/// the buttons and slider that control autoplay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    PlayPause,
+    Step,
+    Rate,
+}
+
+impl Control {
+    const ALL: [Self; 3] = [Self::PlayPause, Self::Step, Self::Rate];
+
+    /// where the controls are anchored, in screen space.
+    const ANCHOR: Vec2 = Vec2 { x: 0.6, y: -0.9 };
+    /// how many control units fit in a unit of screen space.
+    const SCALE: f32 = 20.0;
+    /// where the rate's label goes, compared to the slider.
+    const LABEL_OFFSET: Vec2 = Vec2 { x: 0.0, y: 2.0 };
+
+    /// where the controls are drawn, in screen space.
+    fn matrix() -> Mat4 {
+        shift(Self::ANCHOR.x, Self::ANCHOR.y) * downscale(Self::SCALE)
+    }
+
+    /// how far down the controls reach in screen space, including the rate's label.
+    fn bottom() -> f32 {
+        let (center, _) = Self::Rate.rect();
+        // labels are about as tall as the buttons
+        Self::ANCHOR.y + (center.y + Self::LABEL_OFFSET.y + 1.0) / Self::SCALE
+    }
+
+    /// the center and half size of the control's area.
+    fn rect(self) -> (Vec2, Vec2) {
+        match self {
+            Self::PlayPause => (Vec2::new(-5.0, 0.0), Vec2::ONE),
+            Self::Step => (Vec2::new(-2.5, 0.0), Vec2::ONE),
+            Self::Rate => (Vec2::new(3.0, 0.0), Vec2::new(4.0, 1.0)),
+        }
+    }
+
+    fn at(pos: Vec2) -> Option<Self> {
+        Self::ALL.into_iter().find(|control| {
+            let (center, half) = control.rect();
+            let offset = (pos - center).abs();
+            offset.x <= half.x && offset.y <= half.y
+        })
+    }
+
+    /// the rate the slider would be set to at `pos`.
+    fn rate_at(pos: Vec2) -> usize {
+        let (center, half) = Self::Rate.rect();
+        let along = (pos.x - center.x + half.x) / (2.0 * half.x);
+        (along.clamp(0.0, 1.0) * TICK_RATES.len() as f32).round() as usize
+    }
+}
+
+/// where stored tiles fly off to, in screen space
+const INVENTORY_POS: Vec2 = Vec2 { x: -0.85, y: -0.85 };
+
+/// tiles sliding from where they were to where the tick put them.
+struct TickAnimation {
+    start: Instant,
+    duration: Duration,
+    /// (tile before, from, to, tile after)
+    moves: Vec<(Tile, TilePos, TilePos, Tile)>,
+    /// stored tiles and where they were taken from
+    stored: Vec<(Tile, TilePos)>,
+    /// destinations, drawn empty until the moves land
+    hidden: HashSet<TilePos>,
+}
+
+impl TickAnimation {
+    fn new(fractal: &Fractal, before: Tile, report: &TickReport, duration: Duration) -> Self {
+        let moves: Vec<_> = report
+            .moves
+            .iter()
+            .map(|(src, dst, _)| (fractal.get_in(before, src), src, dst, fractal.get(dst)))
+            .collect();
+        let stored = report
+            .stored_from
+            .iter()
+            .map(|&pos| (fractal.get_in(before, pos), pos))
+            .collect();
+        Self {
+            start: Instant::now(),
+            duration,
+            hidden: moves.iter().map(|&(_, _, dst, _)| dst).collect(),
+            moves,
+            stored,
+        }
+    }
+
+    /// how far along the animation is, eased, or `None` once it's done.
+    fn progress(&self) -> Option<f32> {
+        let t = self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32();
+        (t < 1.0).then(|| t * t * (3.0 - 2.0 * t))
+    }
+}
+
+/// where a position sits relative to the whole fractal.
+fn pos_matrix(pos: TilePos) -> Mat4 {
+    let w = 1.0;
+    let side = 2.0;
+    let out_r = 3_f32.sqrt() / 3.0 * side;
+    let in_r = out_r / 2.0;
+
+    let transforms = [
+        flip_xy(),
+        shift(0.0, -out_r),
+        shift(w, in_r),
+        shift(-w, in_r),
+    ]
+    .map(|t| downscale(2.0) * t);
+
+    pos.fold(Mat4::IDENTITY, |matrix, subtile| {
+        matrix * transforms[subtile as usize]
+    })
+}
+
+/// blends between two placements, passing through a squash when one is mirrored.
+fn lerp_mat4(a: Mat4, b: Mat4, t: f32) -> Mat4 {
+    let (a_scale, a_rot, a_pos) = a.to_scale_rotation_translation();
+    let (b_scale, b_rot, b_pos) = b.to_scale_rotation_translation();
+    Mat4::from_scale_rotation_translation(
+        a_scale.lerp(b_scale, t),
+        a_rot.slerp(b_rot, t),
+        a_pos.lerp(b_pos, t),
+    )
 }
 
 impl FractalViewElement {
     fn new() -> Self {
+        // the closest rate on the slider, in case TICK_PER_SEC isn't on it
+        let off = |idx: usize| (TICK_RATES[idx] - TICK_PER_SEC).abs();
+        let rate = (0..TICK_RATES.len())
+            .min_by(|&a, &b| off(a).total_cmp(&off(b)))
+            .unwrap();
         Self {
             view_state: ViewState::Shattered,
             frac_cam: FractalCam {
                 camera: upscale(2.0) * shift(0.0, 0.625),
                 ..Default::default()
             },
+            changed: HashSet::new(),
+            animation: None,
+            alpha: 1.0,
+            scheduler: {
+                let mut scheduler = TickScheduler::new(TICK_RATES[rate])
+                    .expect("every rate in TICK_RATES is valid");
+                scheduler.pause();
+                scheduler
+            },
+            rate,
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn is_headless(&self) -> bool {
+        self.rate == TICK_RATES.len()
+    }
+
+    fn set_rate(&mut self, rate: usize) {
+        self.rate = rate.min(TICK_RATES.len());
+        if let Some(&ticks_per_sec) = TICK_RATES.get(self.rate) {
+            self.scheduler
+                .set_ticks_per_sec(ticks_per_sec)
+                .expect("every rate in TICK_RATES is valid");
         }
     }
 
@@ -428,11 +928,12 @@ impl FractalViewElement {
         ctx: &mut Context,
         fractory: &Fractory,
         cache: &FractoryCache,
-        names: &[String],
+        fragments: &FragmentRegistry,
         id: usize,
         tile_fill: TileFill,
         pos: Result<TilePos, usize>,
         hovered: bool,
+        is_active: bool,
         text_tool: TextToolId,
     ) -> ControlFlow<()> {
         enum ColorMode {
@@ -477,9 +978,6 @@ impl FractalViewElement {
         // maybe solve this once you do bevy tbh
         // shift+scroll zooms the mouse cursor, scroll zooms the camera *and* the cursor
 
-        // TODO: fragment coloring should first try to use the fragment sprite,
-        // otherwise use a hash color
-        // these should be specified by the fractal itself
         let color_mode = match tile_fill {
             TileFill::Empty => Greyscale,
             TileFill::Partial => Depth,
@@ -497,11 +995,7 @@ impl FractalViewElement {
                 const PALETTE: &[Color] = &[RED, ORANGE, GOLD, GREEN, BLUE, PURPLE];
                 average(BLACK, PALETTE[pos.map_or(0, |p| p.depth() % PALETTE.len())])
             }
-            Fragment => {
-                // TODO: have a tile palette based on fragments
-                const PALETTE: &[Color] = &[RED, ORANGE, GOLD, GREEN, BLUE, PURPLE];
-                PALETTE[id % PALETTE.len()]
-            }
+            Fragment => fragment_color(fragments, id),
             Greyscale => {
                 // const PALETTE: &[Color] = &[DARKGRAY, GRAY, LIGHTGRAY];
                 // PALETTE[pos.depth() % PALETTE.len()]
@@ -509,16 +1003,34 @@ impl FractalViewElement {
             }
         };
 
+        let color = if pos.is_ok_and(|p| self.changed.contains(&p)) {
+            Color {
+                a: color.a,
+                ..average(color, WHITE)
+            }
+        } else {
+            color
+        };
+        let color = Color {
+            a: color.a * self.alpha,
+            ..color
+        };
+
+        let sprite = match color_mode {
+            Fragment => cache.sprites.get(&id).copied(),
+            _ => None,
+        };
+        // already rotated and flipped by `draw_subtree`, so sprites face the right way
+        let fill = |ctx: &mut Context| match sprite {
+            Some(texture) => ctx.queue_sprite(&TRIANGLE, &TRIANGLE_UVS, texture, color),
+            None => ctx.queue_polygon(&TRIANGLE, color),
+        };
+
         ctx.apply(upscale(self.view_state.scaling()), |ctx| {
-            let is_active = pos.is_ok_and(|p| fractory.activated.contains(p));
             // FIXME: 2 of the same tile transformed differently will draw borders wrong.
             if hovered || is_active {
                 let border_color = if is_active {
-                    if !cache
-                        .biome
-                        .behavior(cache.fragments.behaviors(), id)
-                        .is_empty()
-                    {
+                    if !cache.biome.behavior(&cache.fragments, id).is_empty() {
                         WHITE
                     } else {
                         RED
@@ -527,9 +1039,7 @@ impl FractalViewElement {
                     GRAY
                 };
                 ctx.queue_polygon(&TRIANGLE, border_color);
-                ctx.apply(upscale(0.8), |ctx| {
-                    ctx.queue_polygon(&TRIANGLE, color);
-                });
+                ctx.apply(upscale(0.8), fill);
                 if hovered {
                     ctx.apply(
                         shift(0.0, -0.625) * downscale(8.0) * rotate_cw(TAU / 4.0),
@@ -539,13 +1049,13 @@ impl FractalViewElement {
                     )
                 }
             } else {
-                ctx.queue_polygon(&TRIANGLE, color);
+                fill(ctx);
             }
             // ctx.apply(shift(0.0, -0.2) * downscale(4.0), |_| {
             //     let text = format!("{pos:#?}");
             //     text_tool(&text);
             // });
-            let name = match names.get(id) {
+            let name = match fragments.name(id) {
                 Some(name) => name.to_owned(),
                 None => id.to_string(),
             };
@@ -560,14 +1070,18 @@ impl FractalViewElement {
         ctx: &mut Context,
         fractory: &Fractory,
         cache: &FractoryCache,
-        names: &[String],
+        fragments: &FragmentRegistry,
         cur_orient: Transform,
         tile: Tile,
         pos: Result<TilePos, usize>,
+        any_active: bool,
         text_tool: TextToolId,
     ) {
         let mouse = ctx.mouse_pos().unwrap_or(Vec2::ZERO);
         let hovered = in_triangle(mouse);
+        // once nothing below is active, stop asking
+        let any_active = any_active && pos.is_ok_and(|p| fractory.activated.any_under(p));
+        let is_active = any_active && pos.is_ok_and(|p| fractory.activated.contains(p));
         let SlotInfo {
             quad,
             fill,
@@ -593,8 +1107,16 @@ impl FractalViewElement {
             if !ctx.is_onscreen(&TRIANGLE) {
                 return;
             }
+            // whatever lands here is still on its way
+            if let (Ok(pos), Some(animation)) = (pos, &self.animation) {
+                if animation.hidden.contains(&pos) {
+                    let scaling = upscale(self.view_state.scaling());
+                    ctx.apply(scaling, |ctx| ctx.queue_polygon(&TRIANGLE, DARKGRAY));
+                    return;
+                }
+            }
             match self.draw_leaf(
-                ctx, fractory, cache, names, tile.id, fill, pos, hovered, text_tool,
+                ctx, fractory, cache, fragments, tile.id, fill, pos, hovered, is_active, text_tool,
             ) {
                 ControlFlow::Continue(()) => {}
                 ControlFlow::Break(()) => return,
@@ -612,7 +1134,9 @@ impl FractalViewElement {
                     Err(d) => Err(d + 1),
                 };
                 ctx.apply(transform, |ctx| {
-                    self.draw_subtree(ctx, fractory, cache, names, orient, child, pos, text_tool);
+                    self.draw_subtree(
+                        ctx, fractory, cache, fragments, orient, child, pos, any_active, text_tool,
+                    );
                 });
             }
         });
@@ -638,13 +1162,16 @@ impl FractalViewElement {
                 ctx,
                 &fractory_meta.fractory,
                 cache,
-                cache.fragments.names(),
+                &cache.fragments,
                 Transform::KU,
                 fractory_meta.fractory.fractal.root,
                 Ok(TilePos::UNIT),
+                true,
                 text_tool,
             );
+            self.draw_animation(ctx, fractory_meta, cache, text_tool);
         });
+        self.draw_controls(ctx, text_tool);
         ctx.apply(shift(0.0, -0.7) * downscale(5.0), |ctx| {
             let FractalCam {
                 camera,
@@ -667,6 +1194,102 @@ impl FractalViewElement {
         ctx.flush();
     }
 
+    /// draws the tiles still moving from the last tick, on top of the fractal.
+    fn draw_animation(
+        &mut self,
+        ctx: &mut Context,
+        fractory_meta: &FractoryMeta,
+        cache: &FractoryCache,
+        text_tool: TextToolId,
+    ) {
+        let Some(animation) = &self.animation else {
+            return;
+        };
+        let Some(t) = animation.progress() else {
+            self.animation = None;
+            return;
+        };
+
+        let moving: Vec<_> = animation
+            .moves
+            .iter()
+            .map(|&(before, src, dst, after)| {
+                // `draw_subtree` orients the tile itself, so undo that at the destination
+                let end = pos_matrix(dst)
+                    * transform_to_mat4(after.orient.into())
+                    * transform_to_mat4(before.orient.into()).inverse();
+                (lerp_mat4(pos_matrix(src), end, t), before, dst.depth())
+            })
+            .collect();
+        let inventory = self.frac_cam.camera.inverse()
+            * shift(INVENTORY_POS.x, INVENTORY_POS.y)
+            * downscale(16.0);
+        let stored: Vec<_> = animation
+            .stored
+            .iter()
+            .map(|&(tile, src)| (lerp_mat4(pos_matrix(src), inventory, t), tile, src.depth()))
+            .collect();
+
+        for (tiles, alpha) in [(moving, 1.0), (stored, 1.0 - t)] {
+            self.alpha = alpha;
+            for (matrix, tile, depth) in tiles {
+                ctx.apply(matrix, |ctx| {
+                    self.draw_subtree(
+                        ctx,
+                        &fractory_meta.fractory,
+                        cache,
+                        &cache.fragments,
+                        Transform::KU,
+                        tile,
+                        Err(depth),
+                        false,
+                        text_tool,
+                    );
+                });
+            }
+        }
+        self.alpha = 1.0;
+    }
+
+    fn draw_controls(&self, ctx: &mut Context, text_tool: TextToolId) {
+        let rect = |ctx: &mut Context, (center, half): (Vec2, Vec2), color| {
+            ctx.apply(shift(center.x, center.y) * scale(half.x, half.y), |ctx| {
+                ctx.queue_polygon(&SQUARE, color)
+            })
+        };
+        let label = |ctx: &mut Context, center: Vec2, text: &str| {
+            ctx.apply(shift(center.x, center.y), |ctx| {
+                ctx.queue_text(text_tool, text.to_owned())
+            })
+        };
+
+        ctx.apply(Control::matrix(), |ctx| {
+            let (center, half) = Control::PlayPause.rect();
+            rect(ctx, (center, half), DARKGRAY);
+            let icon = if self.scheduler.is_paused() {
+                ">"
+            } else {
+                "||"
+            };
+            label(ctx, center, icon);
+
+            let (center, half) = Control::Step.rect();
+            rect(ctx, (center, half), DARKGRAY);
+            label(ctx, center, ">|");
+
+            let (center, half) = Control::Rate.rect();
+            rect(ctx, (center, half * Vec2::new(1.0, 0.2)), DARKGRAY);
+            let along = self.rate as f32 / TICK_RATES.len() as f32;
+            let handle = Vec2::new(center.x - half.x + 2.0 * half.x * along, center.y);
+            rect(ctx, (handle, Vec2::new(0.3, 0.8)), LIGHTGRAY);
+            let rate = match TICK_RATES.get(self.rate) {
+                Some(ticks_per_sec) => format!("{ticks_per_sec} ticks/s"),
+                None => "max".to_owned(),
+            };
+            label(ctx, center + Control::LABEL_OFFSET, &rate);
+        });
+    }
+
     fn subtree_click_pos(&mut self, click: Vec2, depth: usize) -> Option<TilePos> {
         if depth > self.frac_cam.hover_depth() {
             return None;
@@ -706,12 +1329,20 @@ impl FractalViewElement {
         if click.held {
             return None;
         }
+        self.screen_to_pos(click.pos)
+    }
+
+    /// the position under the mouse, at the hover depth.
+    fn hover_pos(&mut self, ctx: &mut Context) -> Option<TilePos> {
+        self.screen_to_pos(ctx.mouse_pos()?)
+    }
 
+    fn screen_to_pos(&mut self, pos: Vec2) -> Option<TilePos> {
         let pos = self
             .frac_cam
             .camera
             .inverse()
-            .transform_point3(click.pos.extend(0.0))
+            .transform_point3(pos.extend(0.0))
             .truncate();
 
         if !in_triangle(pos) {
@@ -721,95 +1352,247 @@ impl FractalViewElement {
         self.subtree_click_pos(pos, 0)
     }
 
-    fn input_edit(&mut self, hit_pos: TilePos, fractal: &mut Fractal, biome: &Biome) {
-        let increment = if is_mouse_button_released(MouseButton::Left) {
-            1
-        } else if is_mouse_button_released(MouseButton::Right) {
-            biome.leaf_count() - 1
-        } else {
-            debug_assert!(false, "unreachable");
-            return;
+    /// paints the brush wherever the mouse drags with Ctrl+Shift held,
+    /// or picks it up from a tile with Ctrl+Shift+RMB.
+    fn input_paint(
+        &mut self,
+        ctx: &mut Context,
+        fractory: &Fractory,
+        biome: &Biome,
+        palette_view: &mut PaletteViewElement,
+    ) -> ControlFlow<Option<Command>> {
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        let left = is_mouse_button_down(MouseButton::Left);
+        let right = is_mouse_button_down(MouseButton::Right)
+            || is_mouse_button_released(MouseButton::Right);
+        if !(ctrl && shift && (left || right)) {
+            return ControlFlow::Continue(());
+        }
+        let Some(pos) = self.hover_pos(ctx) else {
+            return ControlFlow::Break(None);
         };
 
-        let mut tile = fractal.get(hit_pos);
-        tile.id += increment;
-        tile.id %= biome.leaf_count();
-        tile.orient = fractal.library[tile.id].symmetries.into();
-        fractal.set(hit_pos, tile);
+        let fractal = &fractory.fractal;
+        if right {
+            let tile = fractal.get(pos);
+            if is_mouse_button_released(MouseButton::Right)
+                && biome.fragment_filter().allows(tile.id)
+            {
+                palette_view.brush = Brush {
+                    id: tile.id,
+                    transform: tile.orient.into(),
+                };
+            }
+            return ControlFlow::Break(None);
+        }
+
+        // only paint what would change, so dragging doesn't flood the history
+        let tile = palette_view.brush.tile(fractal);
+        ControlFlow::Break((fractal.get(pos) != tile).then_some(Command::SetTile(pos, tile)))
     }
 
-    fn input_flip(&mut self, hit_pos: TilePos, fractal: &mut Fractal) {
-        let tile = fractal.get(hit_pos);
-        fractal.set(hit_pos, tile + Transform::FU);
+    fn input_flip(&mut self, hit_pos: TilePos) -> Option<Command> {
+        Some(Command::Flip(hit_pos))
     }
 
-    fn input_act(&mut self, hit_pos: TilePos, activated: &mut ActiveTiles) {
-        activated.toggle(hit_pos);
+    fn input_act(&mut self, hit_pos: TilePos) -> Option<Command> {
+        Some(Command::ToggleActive(hit_pos))
     }
 
-    fn input_rot(&mut self, hit_pos: TilePos, fractal: &mut Fractal) {
-        let tf = if is_mouse_button_released(MouseButton::Left) {
-            Transform::KL
+    fn input_rot(&mut self, hit_pos: TilePos) -> Option<Command> {
+        let clockwise = if is_mouse_button_released(MouseButton::Left) {
+            false
         } else if is_mouse_button_released(MouseButton::Right) {
-            Transform::KR
+            true
         } else {
             debug_assert!(false, "unreachable");
-            return;
+            return None;
         };
 
-        let mut tile = fractal.get(hit_pos);
-        tile += tf;
-        fractal.set(hit_pos, tile);
+        Some(Command::Rotate {
+            pos: hit_pos,
+            clockwise,
+        })
     }
 
     fn input(
         &mut self,
         ctx: &mut Context,
         res: &mut Resources,
-        fractory: &mut Fractory,
+        fractory_meta: &mut FractoryMeta,
         cache: &FractoryCache,
+        history: &mut CommandHistory,
+        replay: &mut Replay,
+        inventory_view: &mut InventoryViewElement,
+        palette_view: &mut PaletteViewElement,
     ) {
-        self.frac_cam = (FractalCam::input(ctx) * self.frac_cam).clamp_depth();
+        let fractory = &fractory_meta.fractory;
+        let mut mouse = self.input_controls(ctx);
+        if mouse.is_continue() {
+            mouse = inventory_view.input(ctx, self, fractory);
+        }
+        if mouse.is_continue() {
+            mouse = palette_view.input(ctx, &cache.biome);
+        }
+        if mouse.is_continue() {
+            mouse = self.input_paint(ctx, fractory, &cache.biome, palette_view);
+        }
+        let command = match mouse {
+            ControlFlow::Continue(()) => {
+                self.frac_cam = (FractalCam::input(ctx) * self.frac_cam).clamp_depth();
+                self.input_command(ctx)
+            }
+            // the mouse is busy with the controls, a drag, or painting
+            ControlFlow::Break(command) => command,
+        };
+        self.autoplay(fractory_meta, cache, replay);
 
-        if is_key_pressed(KeyCode::Apostrophe) {
-            // dbg!(&fractory.fractal.library);
-            fractory.rot_cw();
+        let Some(command) = command else {
+            return;
+        };
+        let before = fractory_meta.fractory.fractal.root;
+        let result = fractory_meta.execute(command, &cache.fragments, &cache.biome, history);
+        if result.is_ok() {
+            replay.record(command);
         }
+        match result {
+            Ok(Some(report)) => self.show_tick(&fractory_meta.fractory.fractal, before, &report),
+            Ok(None) => {}
+            Err(error) => println!("{error}"),
+        }
+    }
 
-        if is_key_pressed(KeyCode::Enter) {
-            fractory.tick(&cache.fragments.behaviors(), cache.biome.fragment_filter())
+    /// handles the autoplay controls.
+    /// breaks if the mouse is using them, so it doesn't also click the fractal.
+    fn input_controls(&mut self, ctx: &mut Context) -> ControlFlow<Option<Command>> {
+        if is_key_pressed(KeyCode::P) {
+            self.scheduler.toggle_pause();
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            self.set_rate(self.rate.saturating_sub(1));
+        }
+        if is_key_pressed(KeyCode::Equal) {
+            self.set_rate(self.rate + 1);
         }
 
-        if is_key_pressed(KeyCode::Tab) {
-            self.view_state.cycle();
+        let (mouse, press) = ctx.apply(Control::matrix(), |ctx| (ctx.mouse_pos(), ctx.lmb_pos()));
+        let down = is_mouse_button_down(MouseButton::Left);
+        let released = is_mouse_button_released(MouseButton::Left);
+        let Some(pressed) = press
+            .filter(|_| down || released)
+            .and_then(|(pos, _)| Control::at(pos))
+        else {
+            return ControlFlow::Continue(());
+        };
+        let mouse = mouse.unwrap_or(Vec2::ZERO);
+
+        let mut command = None;
+        match pressed {
+            Control::Rate => self.set_rate(Control::rate_at(mouse)),
+            _ if !released || Control::at(mouse) != Some(pressed) => {}
+            Control::PlayPause => self.scheduler.toggle_pause(),
+            Control::Step => command = Some(Command::Tick),
+        }
+        ControlFlow::Break(command)
+    }
+
+    /// runs every tick that's due since the last frame.
+    fn autoplay(
+        &mut self,
+        fractory_meta: &mut FractoryMeta,
+        cache: &FractoryCache,
+        replay: &mut Replay,
+    ) {
+        let now = Instant::now();
+        let elapsed = now - self.last_frame;
+        self.last_frame = now;
+        if self.scheduler.is_paused() {
+            return;
         }
 
+        // scheduled ticks stay out of the history, like in `fractory_common::run`
+        let mut tick = |fractory_meta: &mut FractoryMeta| {
+            let before = fractory_meta.fractory.fractal.root;
+            let report = fractory_meta.tick(&cache.fragments, &cache.biome);
+            replay.record_tick();
+            (before, report)
+        };
+
+        if self.is_headless() {
+            let before = fractory_meta.fractory.fractal.root;
+            while now.elapsed() < HEADLESS_BUDGET {
+                tick(fractory_meta);
+            }
+            // too fast to animate, so only highlight
+            let fractal = &fractory_meta.fractory.fractal;
+            self.changed = fractal.diff(before, fractal.root).into_iter().collect();
+            self.animation = None;
+            return;
+        }
+
+        let mut last = None;
+        for _ in 0..self.scheduler.advance(elapsed) {
+            last = Some(tick(fractory_meta));
+        }
+        if let Some((before, report)) = last {
+            self.show_tick(&fractory_meta.fractory.fractal, before, &report);
+        }
+    }
+
+    /// highlights what a tick changed, and animates it over one tick's length.
+    fn show_tick(&mut self, fractal: &Fractal, before: Tile, report: &TickReport) {
+        self.changed = fractal.diff(before, fractal.root).into_iter().collect();
+        let duration = self.scheduler.tick_length();
+        self.animation = Some(TickAnimation::new(fractal, before, report, duration));
+    }
+
+    /// turns whatever the player pressed into a command.
+    fn input_command(&mut self, ctx: &mut Context) -> Option<Command> {
         let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
         let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
-        'click: {
-            let click = if is_mouse_button_released(MouseButton::Left) {
-                ctx.get_lmb()
-            } else if is_mouse_button_released(MouseButton::Right) {
-                ctx.get_rmb()
-            } else {
-                None
-            };
-            let Some(hit_pos) = click.and_then(|click| self.tree_click_pos(ctx, click)) else {
-                break 'click;
-            };
 
-            match (ctrl, shift) {
-                (true, true) => self.input_edit(hit_pos, &mut fractory.fractal, &cache.biome),
-                (true, false) => {
-                    if is_mouse_button_released(MouseButton::Left) {
-                        self.input_act(hit_pos, &mut fractory.activated);
-                    } else if is_mouse_button_released(MouseButton::Right) {
-                        self.input_flip(hit_pos, &mut fractory.fractal);
-                    }
+        if is_key_pressed(KeyCode::Tab) {
+            self.view_state.cycle();
+        }
+
+        if is_key_pressed(KeyCode::Apostrophe) {
+            // dbg!(&fractory.fractal.library);
+            return Some(Command::Rotate {
+                pos: TilePos::UNIT,
+                clockwise: true,
+            });
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            return Some(Command::Tick);
+        }
+
+        if ctrl && is_key_pressed(KeyCode::Z) {
+            return Some(Command::Undo);
+        }
+
+        let click = if is_mouse_button_released(MouseButton::Left) {
+            ctx.get_lmb()
+        } else if is_mouse_button_released(MouseButton::Right) {
+            ctx.get_rmb()
+        } else {
+            None
+        };
+        let hit_pos = click.and_then(|click| self.tree_click_pos(ctx, click))?;
+
+        match (ctrl, shift) {
+            (true, false) => {
+                if is_mouse_button_released(MouseButton::Left) {
+                    self.input_act(hit_pos)
+                } else if is_mouse_button_released(MouseButton::Right) {
+                    self.input_flip(hit_pos)
+                } else {
+                    None
                 }
-                (false, true) => self.input_rot(hit_pos, &mut fractory.fractal),
-                _ => {}
             }
+            (false, true) => self.input_rot(hit_pos),
+            _ => None,
         }
     }
 }
@@ -828,6 +1611,22 @@ fn in_triangle(Vec2 { x, y }: Vec2) -> bool {
     (top..bot).contains(&y)
 }
 
+/// loads the sprite of every fragment that has one.
+/// fragments whose sprites can't be loaded are drawn with just their color.
+async fn load_sprites(fragments: &FragmentRegistry) -> HashMap<usize, Texture2D> {
+    let mut out = HashMap::new();
+    for (id, fragment) in fragments.iter() {
+        let Some(path) = &fragment.appearance.sprite else {
+            continue;
+        };
+        match load_texture(path).await {
+            Ok(texture) => drop(out.insert(id, texture)),
+            Err(error) => println!("couldn't load sprite {path:?}: {error:?}"),
+        }
+    }
+    out
+}
+
 fn transform_to_mat4(transform: Transform) -> Mat4 {
     let mut matrix = Mat4::IDENTITY;
     if transform.reflected() {
@@ -857,7 +1656,7 @@ async fn main() {
 
     let mut ctx = Context::default();
     let mut res = Resources::new();
-    let mut ui_elem = UiElement::new(&mut res, font);
+    let mut ui_elem = UiElement::new(&mut res, font).await;
 
     let mut iters = 0;
     let mut time_check = Instant::now();
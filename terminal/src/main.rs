@@ -0,0 +1,51 @@
+//! a text frontend, for editing planets and running factories without a window.
+
+use play::Play;
+use repl::Repl;
+use std::{
+    io::{stdin, stdout, Write},
+    sync::mpsc,
+    thread,
+};
+
+mod play;
+mod repl;
+
+fn main() {
+    // stdin blocks, so it's read on another thread,
+    // which lets ticks keep running while playing
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in stdin().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut repl = Repl::new();
+    println!("{}", repl.help());
+    loop {
+        print!("{}> ", repl.location());
+        stdout().flush().unwrap();
+
+        let Ok(line) = lines.recv() else {
+            // end of input
+            break;
+        };
+        match repl.execute(&line) {
+            Ok(output) => print!("{output}"),
+            Err(error) => println!("{error}"),
+        }
+        if repl.has_quit() {
+            break;
+        }
+        if repl.is_playing() {
+            println!("{}", play::HELP);
+            if let Err(error) = repl.play(&mut Play::new(&lines, stdout())) {
+                println!("{error}");
+            }
+        }
+    }
+}
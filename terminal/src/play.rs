@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests;
+
+use fractory_common::{
+    api::ui::{text_view, Input},
+    sim::logic::{
+        command::{Command, CommandError},
+        factory::{FractoryMeta, TickReport},
+        planet::Planet,
+    },
+    Game,
+};
+use std::{
+    io::Write,
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+/// how many layers down the factory is drawn while playing
+const DEPTH: u8 = 3;
+
+pub const HELP: &str = "\
+playing, with ticks running on their own:
+  s (or nothing)  run a single tick
+  p               pause or resume
+  a <path>        toggle whether a tile is activated
+  r <path>        rotate a tile clockwise
+  u               undo the last command
+  q               go back to the menus";
+
+/// runs the factory in real time through [`fractory_common::run`],
+/// reading commands a line at a time and drawing whenever something changed.
+pub struct Play<'a, W: Write> {
+    /// lines typed by the player, read on another thread since stdin blocks
+    lines: &'a Receiver<String>,
+    out: W,
+    /// whether anything changed since the last draw
+    dirty: bool,
+    ticks: u64,
+}
+
+impl<'a, W: Write> Play<'a, W> {
+    pub fn new(lines: &'a Receiver<String>, out: W) -> Self {
+        Self {
+            lines,
+            out,
+            dirty: true,
+            ticks: 0,
+        }
+    }
+
+    fn parse(line: &str) -> Option<Input> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            None | Some("s") => Command::Tick,
+            Some("p") => return Some(Input::Pause),
+            Some("q") => return Some(Input::Quit),
+            Some("a") => Command::ToggleActive(words.next()?.parse().ok()?),
+            Some("r") => Command::Rotate {
+                pos: words.next()?.parse().ok()?,
+                clockwise: true,
+            },
+            Some("u") => Command::Undo,
+            Some(_) => return None,
+        };
+        Some(Input::Command(command))
+    }
+}
+
+impl<W: Write> Game for Play<'_, W> {
+    fn input(&mut self, _meta: &FractoryMeta, _planet: &Planet) -> Vec<Input> {
+        let mut out = vec![];
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => match Self::parse(&line) {
+                    // anything after quitting is meant for the menus
+                    Some(Input::Quit) => {
+                        out.push(Input::Quit);
+                        break;
+                    }
+                    Some(input) => out.push(input),
+                    None => writeln!(self.out, "{HELP}").unwrap(),
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    out.push(Input::Quit);
+                    break;
+                }
+            }
+        }
+        self.dirty |= !out.is_empty();
+        out
+    }
+
+    fn on_tick(&mut self, _meta: &FractoryMeta, _report: &TickReport) {
+        self.ticks += 1;
+        self.dirty = true;
+    }
+
+    fn on_error(&mut self, _meta: &FractoryMeta, error: CommandError) {
+        writeln!(self.out, "{error}").unwrap();
+    }
+
+    fn draw(&mut self, meta: &FractoryMeta, planet: &Planet) {
+        if !std::mem::take(&mut self.dirty) {
+            return;
+        }
+        let fractory = &meta.fractory;
+        writeln!(self.out, "tick {}", self.ticks).unwrap();
+        write!(
+            self.out,
+            "{}",
+            text_view(&fractory.fractal, planet.fragments(), DEPTH)
+        )
+        .unwrap();
+        writeln!(self.out, "{} active", fractory.activated.len()).unwrap();
+        self.out.flush().unwrap();
+    }
+}
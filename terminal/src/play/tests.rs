@@ -0,0 +1,55 @@
+use super::*;
+use crate::repl::Repl;
+use std::sync::mpsc;
+
+/// plays through a repl, with every line already typed.
+fn play(repl: &mut Repl, lines: &[&str]) -> String {
+    let (sender, receiver) = mpsc::channel();
+    for line in lines {
+        sender.send(line.to_string()).unwrap();
+    }
+    // running out of lines quits, like closing stdin
+    drop(sender);
+    let mut out = vec![];
+    repl.play(&mut Play::new(&receiver, &mut out)).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_play() {
+    let mut repl = Repl::new();
+    repl.execute("factory").unwrap();
+    repl.execute("play").unwrap();
+    assert!(repl.is_playing());
+
+    // pausing first keeps scheduled ticks out of the way
+    let out = play(&mut repl, &["p", "a .", "s", "r U", "u", "r L", "jump"]);
+    assert!(!repl.is_playing());
+    assert!(out.starts_with(HELP), "{out}");
+
+    let mut expected = Repl::new();
+    expected.execute("factory").unwrap();
+    for line in ["toggle .", "tick", "rotate L"] {
+        expected.execute(line).unwrap();
+    }
+    assert_eq!(repl.execute("view"), expected.execute("view"));
+
+    // undoing in the menus can't reach back into play
+    assert!(repl.execute("undo").is_err());
+}
+
+#[test]
+fn test_play_errors() {
+    let mut repl = Repl::new();
+    repl.execute("factory").unwrap();
+    let (sender, receiver) = mpsc::channel();
+    for line in ["p", "u", "q", "s"] {
+        sender.send(line.to_string()).unwrap();
+    }
+    let mut out = vec![];
+    repl.play(&mut Play::new(&receiver, &mut out)).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("there is nothing to undo\n"), "{out}");
+    // quitting stops reading, leaving the rest for the menus
+    assert_eq!(receiver.try_recv().as_deref(), Ok("s"));
+}
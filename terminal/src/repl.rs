@@ -0,0 +1,504 @@
+#[cfg(test)]
+mod tests;
+
+use fractory_common::{
+    api::ui::text_view,
+    run,
+    sim::logic::{
+        command::{Command, CommandError, CommandHistory},
+        factory::FractoryMeta,
+        fragment::{FragmentRegistry, RegistryError},
+        orientation::Orient,
+        path::{ParsePosError, TilePos},
+        planet::{BiomeId, Filter, FilterError, Planet, PlanetCache, PlanetId},
+        tile::{Quad, Tile},
+    },
+    Game,
+};
+use std::{fmt::Display, io};
+
+/// how many layers down the factory is drawn, unless asked otherwise
+const VIEW_DEPTH: u8 = 3;
+
+const HELP: &str = "\
+commands:
+  ls                 list what's here
+  <name>, cd <name>  go into a menu
+  ..                 go back
+  /                  go back to the top
+  help               show this
+  quit               quit";
+
+const EDIT_PLANET: &str = "edit name <name>";
+const EDIT_FRAGMENT: &str = "\
+edit name <name>
+edit composition <tile> <tile> <tile> <tile>
+  tiles are <fragment>[:<orient>], such as Flip-Flop:RfU, or #<id>[:<orient>]";
+const EDIT_BIOME: &str = "\
+edit filter <filter>
+  such as: *, !Rotor";
+const FACTORY: &str = "\
+view [depth]               draw the factory
+set <path> <tile>          replace a tile, as <fragment>[:<orient>]
+rotate <path> [ccw]        rotate a tile clockwise, or counterclockwise
+flip <path>                flip a tile
+toggle <path>              toggle whether a tile is activated
+store <path>               move a fragment into the inventory
+place <path> <fragment>    take a fragment out of the inventory
+tick [count]               run some ticks, 1 by default
+undo                       undo the last command
+play                       run the factory in real time
+  paths go inward from the root, such as CURL, or . for the root itself.
+  coordinates such as d3:1,2f work too";
+
+/// a place in the menu tree sketched out in notes.txt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Menu {
+    Root,
+    Planets,
+    Planet(PlanetId),
+    Fragments(PlanetId),
+    Fragment(PlanetId, usize),
+    Composition(PlanetId, usize),
+    Behavior(PlanetId, usize),
+    Biomes(PlanetId),
+    Biome(PlanetId, BiomeId),
+    Factory,
+}
+
+/// why a line couldn't be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplError {
+    /// neither a command nor anything in the current menu
+    Unknown(String),
+    /// the command was used wrong; holds how it should be used
+    Usage(&'static str),
+    /// there's nothing to edit in the current menu
+    NothingToEdit,
+    Pos(ParsePosError),
+    Filter(FilterError),
+    /// the fragment couldn't be edited
+    Fragment(RegistryError),
+    Command(CommandError),
+}
+
+impl Display for ReplError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "{name:?} is not here, try ls or help"),
+            Self::Usage(usage) => write!(f, "usage:\n{usage}"),
+            Self::NothingToEdit => write!(f, "there is nothing to edit here"),
+            Self::Pos(error) => write!(f, "{error}"),
+            Self::Filter(error) => write!(f, "{error}"),
+            Self::Fragment(error) => write!(f, "{error}"),
+            Self::Command(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplError {}
+
+/// the state of a terminal session: which planets are loaded,
+/// the factory being worked on, and which menu the player is in.
+pub struct Repl {
+    planets: PlanetCache,
+    meta: FractoryMeta,
+    history: CommandHistory,
+    /// every menu entered, starting from the root
+    menus: Vec<Menu>,
+    quit: bool,
+    /// whether the player asked to run the factory in real time
+    playing: bool,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let mut planets = PlanetCache::default();
+        let meta = FractoryMeta::new_xyyy(&mut planets);
+        Self {
+            planets,
+            meta,
+            history: CommandHistory::default(),
+            menus: vec![Menu::Root],
+            quit: false,
+            playing: false,
+        }
+    }
+
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// runs the factory through `game` until it quits.
+    pub fn play<G: Game>(&mut self, game: &mut G) -> io::Result<()> {
+        self.playing = false;
+        // commands while playing have their own history,
+        // so undoing afterwards would skip back over them
+        self.history.clear();
+        run(game, &self.planets, &mut self.meta)
+    }
+
+    /// where the player is in the menu tree, such as `fractory/planets/XYYY`.
+    pub fn location(&self) -> String {
+        let names = self.menus.iter().map(|menu| match menu {
+            Menu::Root => "fractory",
+            Menu::Planets => "planets",
+            Menu::Planet(planet) => planet.as_str(),
+            Menu::Fragments(_) => "fragments",
+            Menu::Fragment(planet, id) => self.planet(planet).fragments().name(*id).unwrap(),
+            Menu::Composition(..) => "composition",
+            Menu::Behavior(..) => "behavior",
+            Menu::Biomes(_) => "biomes",
+            Menu::Biome(_, biome) => biome.as_str(),
+            Menu::Factory => "factory",
+        });
+        names.collect::<Vec<_>>().join("/")
+    }
+
+    /// lists every command that works in the current menu.
+    pub fn help(&self) -> String {
+        let extra = match self.menu() {
+            Menu::Planet(_) => EDIT_PLANET,
+            Menu::Fragment(..) | Menu::Composition(..) => EDIT_FRAGMENT,
+            Menu::Biome(..) => EDIT_BIOME,
+            Menu::Factory => FACTORY,
+            _ => "",
+        };
+        format!("{HELP}\n{extra}\n")
+    }
+
+    /// executes a single line of input, and returns what to print.
+    pub fn execute(&mut self, line: &str) -> Result<String, ReplError> {
+        let line = line.trim();
+        let (word, rest) = line
+            .split_once(' ')
+            .map_or((line, ""), |(word, rest)| (word, rest.trim()));
+        match word {
+            "" => Ok(String::new()),
+            "help" => Ok(self.help()),
+            "quit" => {
+                self.quit = true;
+                Ok(String::new())
+            }
+            "ls" => Ok(self.list()),
+            ".." => {
+                if self.menus.len() > 1 {
+                    self.menus.pop();
+                }
+                Ok(String::new())
+            }
+            "/" => {
+                self.menus.truncate(1);
+                Ok(String::new())
+            }
+            "cd" => self.enter(rest),
+            "edit" => self.edit(rest),
+            _ if *self.menu() == Menu::Factory => self.factory(word, rest),
+            _ => self.enter(line),
+        }
+    }
+
+    fn menu(&self) -> &Menu {
+        self.menus.last().unwrap()
+    }
+
+    /// only planets that are in the cache can be entered, so this can't fail.
+    fn planet(&self, id: &PlanetId) -> &Planet {
+        self.planets.get(id).unwrap()
+    }
+
+    fn planet_mut(&mut self, id: &PlanetId) -> &mut Planet {
+        self.planets.get_mut(id).unwrap()
+    }
+
+    fn enter(&mut self, name: &str) -> Result<String, ReplError> {
+        let unknown = || ReplError::Unknown(name.to_owned());
+        let next = match self.menu().clone() {
+            Menu::Root => match name {
+                "planets" => Menu::Planets,
+                "factory" => Menu::Factory,
+                _ => return Err(unknown()),
+            },
+            Menu::Planets => {
+                let planet = PlanetId::from(name);
+                self.planets.get(&planet).ok_or_else(unknown)?;
+                Menu::Planet(planet)
+            }
+            Menu::Planet(planet) => match name {
+                "fragments" => Menu::Fragments(planet),
+                "biomes" => Menu::Biomes(planet),
+                _ => return Err(unknown()),
+            },
+            Menu::Fragments(planet) => {
+                let fragments = self.planet(&planet).fragments();
+                let id = fragment_id(name, fragments).ok_or_else(unknown)?;
+                Menu::Fragment(planet, id)
+            }
+            Menu::Fragment(planet, id) => match name {
+                "composition" => Menu::Composition(planet, id),
+                "behavior" => Menu::Behavior(planet, id),
+                _ => return Err(unknown()),
+            },
+            Menu::Biomes(planet) => {
+                let biome = BiomeId::from(name);
+                self.planet(&planet)
+                    .biomes()
+                    .get(&biome)
+                    .ok_or_else(unknown)?;
+                Menu::Biome(planet, biome)
+            }
+            Menu::Composition(..) | Menu::Behavior(..) | Menu::Biome(..) | Menu::Factory => {
+                return Err(unknown())
+            }
+        };
+        self.menus.push(next);
+        Ok(String::new())
+    }
+
+    /// shows everything in the current menu.
+    fn list(&self) -> String {
+        let mut out = vec![];
+        match self.menu() {
+            Menu::Root => out.extend(["> planets".into(), "> factory".into()]),
+            Menu::Planets => {
+                let mut planets = self.planets.iter().collect::<Vec<_>>();
+                planets.sort();
+                out.extend(
+                    planets
+                        .iter()
+                        .map(|planet| format!("> {}", planet.as_str())),
+                );
+            }
+            Menu::Planet(planet) => {
+                let planet = self.planet(planet);
+                out.push(format!("name: {}", planet.name()));
+                out.push(planet.desc().to_owned());
+                out.extend(["> fragments".into(), "> biomes".into()]);
+            }
+            Menu::Fragments(planet) => {
+                let fragments = self.planet(planet).fragments().iter().skip(1);
+                out.extend(fragments.map(|(_, fragment)| {
+                    format!("> {} ({})", fragment.name, fragment.appearance.symbol)
+                }));
+            }
+            Menu::Fragment(planet, id) => {
+                let fragment = self.planet(planet).fragments().get(*id).unwrap();
+                out.push(format!("name: {}", fragment.name));
+                let appearance = &fragment.appearance;
+                let [r, g, b] = appearance.color;
+                out.push(format!("symbol: {}", appearance.symbol));
+                out.push(format!("color: #{r:02x}{g:02x}{b:02x}"));
+                if let Some(sprite) = &appearance.sprite {
+                    out.push(format!("sprite: {sprite}"));
+                }
+                out.push(fragment.desc.clone());
+                out.extend(["> composition".into(), "> behavior".into()]);
+            }
+            Menu::Composition(planet, id) => {
+                let fragments = self.planet(planet).fragments();
+                let fragment = fragments.get(*id).unwrap();
+                let composition = fragment
+                    .composition
+                    .0
+                    .map(|tile| tile_name(tile, fragments));
+                out.push(format!("name: {}", fragment.name));
+                out.push(format!("composition: {}", composition.join(" ")));
+                out.push(format!(
+                    "symmetry: {:?}",
+                    fragments.symmetries(*id).unwrap()
+                ));
+            }
+            Menu::Behavior(planet, id) => {
+                let behavior = self.planet(planet).fragments().behavior(*id).unwrap();
+                if behavior.is_empty() {
+                    out.push("does nothing".into());
+                }
+                out.extend(behavior.iter().map(|action| format!("{action:?}")));
+            }
+            Menu::Biomes(planet) => {
+                let mut biomes = self.planet(planet).biomes().iter().collect::<Vec<_>>();
+                biomes.sort();
+                out.extend(biomes.iter().map(|biome| format!("> {}", biome.as_str())));
+            }
+            Menu::Biome(planet, biome) => {
+                let planet = self.planet(planet);
+                let biome = planet.biomes().get(biome).unwrap();
+                let filter = biome.fragment_filter().display(planet.fragments());
+                out.push(format!("name: {}", biome.name()));
+                out.push(biome.desc().to_owned());
+                out.push(format!("filter: {filter}"));
+                out.push(format!("collisions: {:?}", biome.collision_policy()));
+                for mission in biome.missions() {
+                    out.push(format!("mission {}: {}", mission.name, mission.desc));
+                }
+            }
+            Menu::Factory => return self.view(VIEW_DEPTH),
+        }
+        lines(out)
+    }
+
+    fn edit(&mut self, rest: &str) -> Result<String, ReplError> {
+        let (field, value) = rest
+            .split_once(' ')
+            .map_or((rest, ""), |(field, value)| (field, value.trim()));
+        match (self.menu().clone(), field) {
+            (Menu::Planet(_), _) if value.is_empty() => return Err(ReplError::Usage(EDIT_PLANET)),
+            (Menu::Planet(planet), "name") => self.planet_mut(&planet).set_name(value.into()),
+            (Menu::Planet(_), _) => return Err(ReplError::Usage(EDIT_PLANET)),
+
+            (Menu::Fragment(planet, id) | Menu::Composition(planet, id), "name")
+                if !value.is_empty() =>
+            {
+                let fragments = self.planet_mut(&planet).fragments_mut();
+                fragments
+                    .rename(id, value.into())
+                    .map_err(ReplError::Fragment)?;
+            }
+            (Menu::Fragment(planet, id) | Menu::Composition(planet, id), "composition") => {
+                let fragments = self.planet_mut(&planet).fragments_mut();
+                let tiles = value
+                    .split_whitespace()
+                    .map(|word| parse_tile(word, fragments))
+                    .collect::<Option<Vec<_>>>();
+                let composition = tiles
+                    .and_then(|tiles| tiles.try_into().ok())
+                    .ok_or(ReplError::Usage(EDIT_FRAGMENT))?;
+                fragments
+                    .set_composition(id, Quad(composition))
+                    .map_err(ReplError::Fragment)?;
+                if self.meta.planet == planet {
+                    let fractal = self.planet(&planet).fragments().build_fractal();
+                    self.meta
+                        .fractory
+                        .rebuild(fractal.expect("set_composition checks that it builds"));
+                    // undoing would bring back roots from the old fractal
+                    self.history.clear();
+                }
+            }
+            (Menu::Fragment(..) | Menu::Composition(..), _) => {
+                return Err(ReplError::Usage(EDIT_FRAGMENT))
+            }
+
+            (Menu::Biome(planet, biome), "filter") => {
+                let planet = self.planet_mut(&planet);
+                let filter = Filter::parse(value, planet.fragments()).map_err(ReplError::Filter)?;
+                let biome = planet.biomes_mut().get_mut(&biome).unwrap();
+                biome.set_fragment_filter(filter);
+            }
+            (Menu::Biome(..), _) => return Err(ReplError::Usage(EDIT_BIOME)),
+
+            _ => return Err(ReplError::NothingToEdit),
+        }
+        Ok(self.list())
+    }
+
+    /// runs a command on the factory, then draws it.
+    fn factory(&mut self, word: &str, rest: &str) -> Result<String, ReplError> {
+        let planet = self.planets.get(&self.meta.planet).unwrap();
+        let fragments = planet.fragments();
+        let usage = || ReplError::Usage(FACTORY);
+        let mut args = rest.split_whitespace();
+        // almost every command starts with a path
+        let pos = match args.next() {
+            Some(word) => word.parse::<TilePos>().map_err(ReplError::Pos),
+            None => Err(usage()),
+        };
+
+        let (command, count) = match word {
+            "view" if rest.is_empty() => return Ok(self.view(VIEW_DEPTH)),
+            "view" => return Ok(self.view(rest.parse().map_err(|_| usage())?)),
+            "set" => {
+                let pos = pos?;
+                let tile = args.next().and_then(|word| parse_tile(word, fragments));
+                (Command::SetTile(pos, tile.ok_or_else(usage)?), 1)
+            }
+            "rotate" => {
+                let pos = pos?;
+                let clockwise = match args.next() {
+                    None => true,
+                    Some("ccw") => false,
+                    Some(_) => return Err(usage()),
+                };
+                (Command::Rotate { pos, clockwise }, 1)
+            }
+            "flip" => (Command::Flip(pos?), 1),
+            "toggle" => (Command::ToggleActive(pos?), 1),
+            "store" => (Command::Store(pos?), 1),
+            "place" => {
+                let pos = pos?;
+                let id = args.next().and_then(|word| fragment_id(word, fragments));
+                (Command::Place(pos, id.ok_or_else(usage)?), 1)
+            }
+            "tick" if rest.is_empty() => (Command::Tick, 1),
+            "tick" => (Command::Tick, rest.parse().map_err(|_| usage())?),
+            "undo" => (Command::Undo, 1),
+            "play" => {
+                self.playing = true;
+                return Ok(String::new());
+            }
+            _ => return Err(ReplError::Unknown(word.into())),
+        };
+
+        let biome = planet.biomes().get(&self.meta.biome).unwrap();
+        for _ in 0..count {
+            self.meta
+                .execute(command, fragments, biome, &mut self.history)
+                .map_err(ReplError::Command)?;
+        }
+        Ok(self.view(VIEW_DEPTH))
+    }
+
+    fn view(&self, depth: u8) -> String {
+        let fragments = self.planet(&self.meta.planet).fragments();
+        let fractory = &self.meta.fractory;
+        let mut out = vec![text_view(&fractory.fractal, fragments, depth)];
+        out.push(format!("{} active", fractory.activated.len()));
+        for (&id, count) in &fractory.inventory {
+            let name = fragments.name(id).unwrap_or("?");
+            out.push(format!("{name}: {count}"));
+        }
+        lines(out)
+    }
+}
+
+/// joins lines of output, ending with a newline.
+fn lines(lines: Vec<String>) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// finds a fragment by name, or by id as `#3`.
+fn fragment_id(word: &str, fragments: &FragmentRegistry) -> Option<usize> {
+    match word.strip_prefix('#') {
+        Some(id) => id.parse().ok().filter(|&id| id < fragments.len()),
+        None => fragments.id(word),
+    }
+}
+
+/// writes a tile the same way `parse_tile` reads it.
+fn tile_name(tile: Tile, fragments: &FragmentRegistry) -> String {
+    match fragments.name(tile.id) {
+        Some(name) if !name.is_empty() => format!("{name}:{:?}", tile.orient),
+        _ => format!("#{}:{:?}", tile.id, tile.orient),
+    }
+}
+
+/// reads a tile as `<fragment>[:<orient>]`.
+/// without an orientation, it's oriented like the fragment's composition.
+fn parse_tile(word: &str, fragments: &FragmentRegistry) -> Option<Tile> {
+    let orient = word.rsplit_once(':').and_then(|(name, orient)| {
+        let orient = Orient::ORIENTATIONS
+            .into_iter()
+            .find(|o| format!("{o:?}") == orient)?;
+        Some((name, orient))
+    });
+    let Some((name, orient)) = orient else {
+        let id = fragment_id(word, fragments)?;
+        return fragments.tile(fragments.name(id)?);
+    };
+    let id = fragment_id(name, fragments)?;
+    Some(Tile { id, orient })
+}
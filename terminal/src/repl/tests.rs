@@ -0,0 +1,121 @@
+use super::*;
+use fractory_common::sim::logic::tile::SubTile;
+
+/// runs every line, and returns the output of the last one.
+fn run(repl: &mut Repl, lines: &[&str]) -> Result<String, ReplError> {
+    let (last, lines) = lines.split_last().unwrap();
+    for line in lines {
+        repl.execute(line).unwrap();
+    }
+    repl.execute(last)
+}
+
+#[test]
+fn test_menus() {
+    let mut repl = Repl::new();
+    let out = run(&mut repl, &["planets", "ls"]).unwrap();
+    assert_eq!(out, "> XYYY\n");
+
+    let out = run(&mut repl, &["XYYY", "cd fragments", "X", "ls"]).unwrap();
+    assert!(
+        out.contains("color: #ffa100\nsprite: sprites/boccs.png"),
+        "{out}"
+    );
+
+    run(&mut repl, &["..", "Spinner", "composition"]).unwrap();
+    assert_eq!(
+        repl.location(),
+        "fractory/planets/XYYY/fragments/Spinner/composition"
+    );
+    let out = repl.execute("ls").unwrap();
+    assert!(
+        out.contains("composition: Flip-Flop:RfU X:Iso Y:Iso X:Iso"),
+        "{out}"
+    );
+
+    run(&mut repl, &["..", "..", "..", "biomes", "Landing Zone"]).unwrap();
+    assert_eq!(repl.location(), "fractory/planets/XYYY/biomes/Landing Zone");
+
+    repl.execute("/").unwrap();
+    assert_eq!(repl.location(), "fractory");
+    assert_eq!(
+        repl.execute("nowhere"),
+        Err(ReplError::Unknown("nowhere".into()))
+    );
+    assert_eq!(repl.execute("edit name Z"), Err(ReplError::NothingToEdit));
+}
+
+#[test]
+fn test_edit() {
+    let mut repl = Repl::new();
+    run(&mut repl, &["planets", "XYYY", "fragments", "Wire"]).unwrap();
+    assert_eq!(
+        repl.execute("edit name X"),
+        Err(ReplError::Fragment(RegistryError::NameTaken("X".into())))
+    );
+    repl.execute("edit name Cable").unwrap();
+    assert_eq!(repl.location(), "fractory/planets/XYYY/fragments/Cable");
+
+    repl.execute("edit composition Y X Y:Iso #2").unwrap();
+    let out = run(&mut repl, &["composition", "ls"]).unwrap();
+    assert!(
+        out.contains("composition: Y:Iso X:Iso Y:Iso Y:Iso"),
+        "{out}"
+    );
+    // the factory is rebuilt to match
+    let fragments = repl.planet(&repl.meta.planet).fragments();
+    let cable = fragments.tile("Cable").unwrap();
+    let fractal = &repl.meta.fractory.fractal;
+    assert_eq!(
+        SubTile::QUAD.map(|subtile| fractal.get_in(cable, TilePos::from_inward_path([subtile]))),
+        fragments.by_name("Cable").unwrap().composition
+    );
+    // the same as Flip-Flop, turned around
+    assert!(matches!(
+        repl.execute("edit composition X Y X Y"),
+        Err(ReplError::Fragment(RegistryError::Duplicate { .. }))
+    ));
+    assert!(matches!(
+        repl.execute("edit composition X Y"),
+        Err(ReplError::Usage(_))
+    ));
+
+    run(&mut repl, &["/", "planets", "XYYY", "biomes", "Spinless"]).unwrap();
+    let out = repl.execute("edit filter *, !Cable").unwrap();
+    assert!(out.contains("filter: *, !Cable"), "{out}");
+    assert!(matches!(
+        repl.execute("edit filter Nothing"),
+        Err(ReplError::Filter(_))
+    ));
+}
+
+#[test]
+fn test_factory() {
+    let mut repl = Repl::new();
+    repl.execute("factory").unwrap();
+    let before = repl.meta.fractory.fractal.root;
+
+    run(&mut repl, &["set CU Spinner", "rotate CU ccw", "flip CU"]).unwrap();
+    let spinner = repl.execute("toggle CU").unwrap();
+    assert!(spinner.contains('S'), "{spinner}");
+    repl.execute("tick 3").unwrap();
+    for _ in 0..7 {
+        repl.execute("undo").unwrap();
+    }
+    assert_eq!(repl.meta.fractory.fractal.root, before);
+    assert_eq!(
+        repl.execute("undo"),
+        Err(ReplError::Command(CommandError::NothingToUndo))
+    );
+
+    assert!(matches!(repl.execute("flip CUX"), Err(ReplError::Pos(_))));
+    assert!(matches!(repl.execute("flip"), Err(ReplError::Usage(_))));
+    repl.execute("flip d2:0,3").unwrap();
+    assert!(matches!(
+        repl.execute("tick lots"),
+        Err(ReplError::Usage(_))
+    ));
+    let out = repl.execute("view 1").unwrap();
+    assert_eq!(out.lines().nth(1).unwrap().len(), 3, "{out}");
+    assert!(matches!(repl.execute("view x"), Err(ReplError::Usage(_))));
+}